@@ -0,0 +1,107 @@
+// compares the linear rule scan of map_value, the binary search of MapIndex and the
+// branch-free batches of BatchMap. run with `cargo bench`; the batches only get the wide
+// vector registers with RUSTFLAGS="-C target-cpu=native".
+use aoc5::batch::BatchMap;
+use aoc5::index::MapIndex;
+use aoc5::{map_value, Map};
+use std::hint::black_box;
+use std::time::Instant;
+
+const CATEGORIES: usize = 7;
+const SEEDS: u64 = 2_000_000;
+// seeds handed to the lookups at once.
+const BLOCK: usize = 1024;
+
+// small xorshift generator, the numbers only need to look like a real input.
+fn next(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+// rules that cut 0..4_000_000_000 into pieces and shuffle them around, like the real inputs.
+fn random_maps(state: &mut u64, rules: usize) -> Vec<Map> {
+    let mut cuts: Vec<u64> = (0..rules - 1)
+        .map(|_| next(state) % 4_000_000_000)
+        .collect();
+    cuts.push(0);
+    cuts.push(4_000_000_000);
+    cuts.sort();
+    cuts.dedup();
+
+    let mut maps: Vec<Map> = cuts
+        .windows(2)
+        .map(|pair| Map {
+            destination_range_start: next(state) % 4_000_000_000,
+            source_range_start: pair[0],
+            range_length: pair[1] - pair[0],
+        })
+        .collect();
+    // the inputs are not sorted either.
+    for i in (1..maps.len()).rev() {
+        maps.swap(i, (next(state) % (i as u64 + 1)) as usize);
+    }
+    maps
+}
+
+// every variant gets the seeds in blocks and maps them in place, so that the scalar lookups and
+// the batches pay the same for producing the seeds and for taking the minimum.
+fn measure(name: &str, map_block: impl Fn(&mut [u64])) {
+    let start = Instant::now();
+    let mut smallest = u64::MAX;
+    let mut block = [0u64; BLOCK];
+    for first in (0..SEEDS).step_by(BLOCK) {
+        for (i, seed) in block.iter_mut().enumerate() {
+            *seed = (first + i as u64) * 1999;
+        }
+        map_block(black_box(&mut block));
+        smallest = block
+            .iter()
+            .fold(smallest, |smallest, &value| smallest.min(value));
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{:>8}: {:>8.2?} for {} seeds, {:.1} Mseeds/s (minimum {})",
+        name,
+        elapsed,
+        SEEDS,
+        SEEDS as f64 / elapsed.as_secs_f64() / 1e6,
+        smallest
+    );
+}
+
+fn main() {
+    // the real inputs have a few dozen rules per category, the larger count shows how the
+    // two approaches scale.
+    for rules in [40, 400] {
+        let mut state = 0x2545_f491_4f6c_dd1d;
+        let categories: Vec<Vec<Map>> = (0..CATEGORIES)
+            .map(|_| random_maps(&mut state, rules))
+            .collect();
+        let indexes: Vec<MapIndex> = categories.iter().map(|maps| MapIndex::new(maps)).collect();
+
+        println!("{} rules per category:", rules);
+        let batches: Vec<BatchMap> = categories.iter().map(|maps| BatchMap::new(maps)).collect();
+
+        measure("linear", |block| {
+            for seed in block {
+                *seed = categories
+                    .iter()
+                    .fold(*seed, |value, maps| map_value(value, maps));
+            }
+        });
+        measure("index", |block| {
+            for seed in block {
+                *seed = indexes
+                    .iter()
+                    .fold(*seed, |value, index| index.lookup(value));
+            }
+        });
+        measure("batch", |block| {
+            for batch in &batches {
+                batch.map_slice(block);
+            }
+        });
+    }
+}
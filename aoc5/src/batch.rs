@@ -0,0 +1,127 @@
+use crate::compose::PiecewiseMap;
+use crate::Map;
+
+// values handled together. eight u64 fill an AVX-512 register or two AVX2 ones, and the
+// remainder of a slice is padded up to it.
+pub const LANES: usize = 8;
+
+// a category prepared for mapping many values at once without branches. the offset of a value
+// is the sum of the offset changes at every boundary at or below it, so each boundary costs
+// one comparison and one masked add per value, the same instructions for every lane, which
+// the compiler turns into vector code. the work grows with the number of boundaries, so this
+// pays off for the few dozen rules of the puzzle inputs, MapIndex is better for hundreds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchMap {
+    // sorted segment starts where the offset changes.
+    boundaries: Vec<u64>,
+    // how much the offset changes at each boundary, as a wrapping u64.
+    deltas: Vec<u64>,
+}
+
+impl BatchMap {
+    pub fn new(maps: &[Map]) -> Self {
+        BatchMap::from_function(&PiecewiseMap::from_maps(maps))
+    }
+
+    // works for a composed chain as well, though that usually has many more boundaries.
+    pub fn from_function(function: &PiecewiseMap) -> Self {
+        let mut boundaries = Vec::new();
+        let mut deltas = Vec::new();
+        let mut previous = 0u64;
+        for segment in function.segments() {
+            let offset = segment.destination_start.wrapping_sub(segment.source.start);
+            if offset != previous {
+                boundaries.push(segment.source.start);
+                deltas.push(offset.wrapping_sub(previous));
+                previous = offset;
+            }
+        }
+        // the segments end at u64::MAX, which itself is never moved.
+        if previous != 0 {
+            boundaries.push(u64::MAX);
+            deltas.push(0u64.wrapping_sub(previous));
+        }
+        BatchMap { boundaries, deltas }
+    }
+
+    pub fn boundaries(&self) -> usize {
+        self.boundaries.len()
+    }
+
+    fn map_lanes(&self, values: &mut [u64; LANES]) {
+        let mut shifts = [0u64; LANES];
+        for (&boundary, &delta) in self.boundaries.iter().zip(&self.deltas) {
+            for (shift, &value) in shifts.iter_mut().zip(values.iter()) {
+                // all ones when the value is at or past the boundary, zero otherwise.
+                let mask = 0u64.wrapping_sub((value >= boundary) as u64);
+                *shift = shift.wrapping_add(delta & mask);
+            }
+        }
+        for (value, shift) in values.iter_mut().zip(shifts) {
+            *value = value.wrapping_add(shift);
+        }
+    }
+
+    // map every value in place, the same as map_value on each of them.
+    pub fn map_slice(&self, values: &mut [u64]) {
+        let mut chunks = values.chunks_exact_mut(LANES);
+        for chunk in &mut chunks {
+            self.map_lanes(chunk.try_into().unwrap());
+        }
+        let remainder = chunks.into_remainder();
+        if !remainder.is_empty() {
+            let mut lanes = [0u64; LANES];
+            lanes[..remainder.len()].copy_from_slice(remainder);
+            self.map_lanes(&mut lanes);
+            remainder.copy_from_slice(&lanes[..remainder.len()]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::Rng;
+    use crate::map_value;
+
+    #[test]
+    fn test_batch_matches_map_value() {
+        let mut rng = Rng::new(5);
+        for case in 0..200 {
+            let maps: Vec<Map> = (0..rng.between(0..20))
+                .map(|_| {
+                    let source = rng.between(0..1000);
+                    Map {
+                        destination_range_start: rng.between(0..1000),
+                        source_range_start: source,
+                        range_length: rng.between(0..200),
+                    }
+                })
+                .chain([
+                    // rules that touch both ends of the 64-bit range.
+                    Map {
+                        destination_range_start: u64::MAX - 10,
+                        source_range_start: 3,
+                        range_length: 10,
+                    },
+                    Map {
+                        destination_range_start: 7,
+                        source_range_start: u64::MAX - 5,
+                        range_length: 5,
+                    },
+                ])
+                .collect();
+            let batch = BatchMap::new(&maps);
+
+            // an odd length, so the padded remainder is used as well.
+            let mut values: Vec<u64> = (0..1203).collect();
+            values.extend((0..8).map(|i| u64::MAX - i));
+            let expected: Vec<u64> = values
+                .iter()
+                .map(|&value| map_value(value, &maps))
+                .collect();
+            batch.map_slice(&mut values);
+            assert_eq!(values, expected, "case {}", case);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use crate::Almanac;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+// "seed-to-soil" -> ("seed", "soil")
+pub fn split_map_name(map_name: &str) -> Option<(&str, &str)> {
+    let (source, destination) = map_name.split_once("-to-")?;
+    if source.is_empty() || destination.is_empty() {
+        return None;
+    }
+    Some((source, destination))
+}
+
+// which categories can be converted into which, built from the map names of an almanac.
+#[derive(Debug, Default)]
+pub struct CategoryGraph {
+    // source category -> (destination category, map name), sorted by destination.
+    edges: HashMap<String, Vec<(String, String)>>,
+}
+
+impl CategoryGraph {
+    pub fn from_almanac(almanac: &Almanac) -> Self {
+        let mut graph = CategoryGraph::default();
+        for map_name in almanac.maps.keys() {
+            if let Some((source, destination)) = split_map_name(map_name) {
+                graph
+                    .edges
+                    .entry(source.to_string())
+                    .or_default()
+                    .push((destination.to_string(), map_name.clone()));
+            }
+        }
+        for edges in graph.edges.values_mut() {
+            edges.sort();
+        }
+        graph
+    }
+
+    pub fn categories(&self) -> Vec<&str> {
+        let mut categories: Vec<&str> = self
+            .edges
+            .iter()
+            .flat_map(|(source, edges)| {
+                std::iter::once(source.as_str())
+                    .chain(edges.iter().map(|(destination, _)| destination.as_str()))
+            })
+            .collect();
+        categories.sort();
+        categories.dedup();
+        categories
+    }
+
+    // every map name, ordered along the chain: a map comes after the maps that lead into its
+    // source category. ties and cycles are broken by name, so the order is stable.
+    pub fn ordered_map_names(&self) -> Vec<String> {
+        let mut incoming: HashMap<&str, usize> = self
+            .categories()
+            .into_iter()
+            .map(|category| (category, 0))
+            .collect();
+        for (destination, _) in self.edges.values().flatten() {
+            *incoming.get_mut(destination.as_str()).unwrap() += 1;
+        }
+        let mut ready: BTreeSet<&str> = incoming
+            .iter()
+            .filter(|(_, &count)| count == 0)
+            .map(|(&category, _)| category)
+            .collect();
+
+        let mut visited = HashSet::new();
+        let mut map_names = Vec::new();
+        loop {
+            let category = match ready.pop_first() {
+                Some(category) => category,
+                // only cycles are left, continue with the smallest category not yet visited.
+                None => match incoming
+                    .keys()
+                    .filter(|category| !visited.contains(*category))
+                    .min()
+                {
+                    Some(&category) => category,
+                    None => break,
+                },
+            };
+            if !visited.insert(category) {
+                continue;
+            }
+            for (destination, map_name) in self.edges.get(category).into_iter().flatten() {
+                map_names.push(map_name.clone());
+                let count = incoming.get_mut(destination.as_str()).unwrap();
+                *count = count.saturating_sub(1);
+                if *count == 0 && !visited.contains(destination.as_str()) {
+                    ready.insert(destination);
+                }
+            }
+        }
+        map_names
+    }
+
+    // the map names that lead from one category to another, e.g. soil -> humidity.
+    // if there are several routes, the one with the fewest maps is returned.
+    pub fn path(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let mut previous: HashMap<&str, (&str, &str)> = HashMap::new();
+        let mut visited = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                let mut map_names = Vec::new();
+                let mut current = to;
+                while let Some(&(source, map_name)) = previous.get(current) {
+                    map_names.push(map_name.to_string());
+                    current = source;
+                }
+                map_names.reverse();
+                return Some(map_names);
+            }
+            for (destination, map_name) in self.edges.get(category).into_iter().flatten() {
+                if visited.insert(destination) {
+                    previous.insert(destination, (category, map_name));
+                    queue.push_back(destination);
+                }
+            }
+        }
+        None
+    }
+
+    // the routes from one category to another that visit no category twice, as map names.
+    // shorter routes come first, routes of the same length are sorted by their map names.
+    // densely connected categories have exponentially many routes, so the search stops once
+    // it found `limit` of them; those are not necessarily the shortest ones.
+    pub fn routes(&self, from: &str, to: &str, limit: usize) -> Vec<Vec<String>> {
+        let mut search = RouteSearch {
+            to,
+            reaching: self.reaching(to),
+            visited: HashSet::from([from]),
+            route: Vec::new(),
+            routes: Vec::new(),
+            limit,
+        };
+        if search.reaching.contains(from) {
+            self.collect_routes(from, &mut search);
+        }
+        let mut routes = search.routes;
+        routes.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+        routes
+    }
+
+    // the categories that have a way to `to`, `to` included.
+    fn reaching<'a>(&'a self, to: &'a str) -> HashSet<&'a str> {
+        let mut incoming: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (source, edges) in &self.edges {
+            for (destination, _) in edges {
+                incoming.entry(destination).or_default().push(source);
+            }
+        }
+        let mut reaching = HashSet::from([to]);
+        let mut queue = VecDeque::from([to]);
+        while let Some(category) = queue.pop_front() {
+            for &source in incoming.get(category).into_iter().flatten() {
+                if reaching.insert(source) {
+                    queue.push_back(source);
+                }
+            }
+        }
+        reaching
+    }
+
+    fn collect_routes<'a>(&'a self, category: &str, search: &mut RouteSearch<'a>) {
+        if category == search.to {
+            search.routes.push(search.route.clone());
+            return;
+        }
+        for (destination, map_name) in self.edges.get(category).into_iter().flatten() {
+            if search.routes.len() == search.limit {
+                return;
+            }
+            // a dead end is not worth walking into.
+            if !search.reaching.contains(destination.as_str())
+                || !search.visited.insert(destination)
+            {
+                continue;
+            }
+            search.route.push(map_name.clone());
+            self.collect_routes(destination, search);
+            search.route.pop();
+            search.visited.remove(destination.as_str());
+        }
+    }
+}
+
+// the state of CategoryGraph::routes while it walks the graph.
+struct RouteSearch<'a> {
+    to: &'a str,
+    reaching: HashSet<&'a str>,
+    visited: HashSet<&'a str>,
+    route: Vec<String>,
+    routes: Vec<Vec<String>>,
+    limit: usize,
+}
+
+// "seed -> soil -> location" for the map names of a route.
+pub fn route_to_string(map_names: &[String]) -> String {
+    let mut categories: Vec<&str> = Vec::new();
+    for (source, destination) in map_names.iter().filter_map(|name| split_map_name(name)) {
+        if categories.is_empty() {
+            categories.push(source);
+        }
+        categories.push(destination);
+    }
+    categories.join(" -> ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path() {
+        let mut almanac = Almanac {
+            seeds: Vec::new(),
+            maps: HashMap::new(),
+        };
+        for map_name in [
+            "dirt-to-water",
+            "seed-to-dirt",
+            "water-to-place",
+            "dirt-to-light",
+        ] {
+            almanac.maps.insert(map_name.to_string(), Vec::new());
+        }
+        let graph = CategoryGraph::from_almanac(&almanac);
+
+        assert_eq!(
+            graph.path("seed", "place"),
+            Some(vec![
+                "seed-to-dirt".to_string(),
+                "dirt-to-water".to_string(),
+                "water-to-place".to_string()
+            ])
+        );
+        assert_eq!(
+            graph.path("dirt", "water"),
+            Some(vec!["dirt-to-water".to_string()])
+        );
+        assert_eq!(graph.path("seed", "seed"), Some(Vec::new()));
+        assert_eq!(graph.path("place", "seed"), None);
+        assert_eq!(
+            graph.ordered_map_names(),
+            vec![
+                "seed-to-dirt",
+                "dirt-to-light",
+                "dirt-to-water",
+                "water-to-place"
+            ]
+        );
+        assert_eq!(
+            graph.categories(),
+            vec!["dirt", "light", "place", "seed", "water"]
+        );
+    }
+
+    #[test]
+    fn test_routes() {
+        let mut almanac = Almanac {
+            seeds: Vec::new(),
+            maps: HashMap::new(),
+        };
+        for map_name in [
+            "seed-to-soil",
+            "seed-to-fertilizer",
+            "soil-to-location",
+            "fertilizer-to-soil",
+            "fertilizer-to-location",
+            // a cycle that no route may run through twice.
+            "location-to-seed",
+        ] {
+            almanac.maps.insert(map_name.to_string(), Vec::new());
+        }
+        let routes = CategoryGraph::from_almanac(&almanac).routes("seed", "location", 10);
+        let routes: Vec<String> = routes.iter().map(|route| route_to_string(route)).collect();
+        assert_eq!(
+            routes,
+            vec![
+                "seed -> fertilizer -> location",
+                "seed -> soil -> location",
+                "seed -> fertilizer -> soil -> location",
+            ]
+        );
+        assert_eq!(
+            CategoryGraph::from_almanac(&almanac).routes("soil", "fertilizer", 10),
+            vec![vec![
+                "soil-to-location".to_string(),
+                "location-to-seed".to_string(),
+                "seed-to-fertilizer".to_string()
+            ]]
+        );
+        assert_eq!(
+            CategoryGraph::from_almanac(&almanac)
+                .routes("seed", "location", 2)
+                .len(),
+            2
+        );
+
+        // every category leads to every later one, so there are 2^37 routes from category0 to
+        // category38. category39 comes after it and is a dead end that is never entered.
+        almanac.maps.clear();
+        for source in 0..40 {
+            for destination in source + 1..40 {
+                almanac.maps.insert(
+                    format!("category{}-to-category{}", source, destination),
+                    Vec::new(),
+                );
+            }
+        }
+        let routes = CategoryGraph::from_almanac(&almanac).routes("category0", "category38", 100);
+        assert_eq!(routes.len(), 100);
+        assert!(routes.iter().all(|route| route.len() < 38));
+    }
+}
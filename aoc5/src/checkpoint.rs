@@ -0,0 +1,168 @@
+use crate::Almanac;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+// version of the file format below, bumped on incompatible changes.
+pub const CHECKPOINT_VERSION: u32 = 2;
+
+// the searches saved in one checkpoint file, written as JSON. a run goes through several files,
+// both parts and every route, so each search is kept under its own fingerprint and resumed on
+// its own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointFile {
+    pub version: u32,
+    // missing in the files of version 1, which held a single search.
+    #[serde(default)]
+    pub searches: Vec<Checkpoint>,
+}
+
+// how far a brute-force search got. every seed below `reached` in a range has been visited; a
+// range is finished once `reached` is its end. `best` is the smallest location seen so far,
+// possibly from seeds past `reached` that a resumed search visits once more.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    // identifies the almanac and the chain of maps, see `fingerprint`.
+    pub fingerprint: u64,
+    pub ranges: Vec<RangeCheckpoint>,
+    pub best: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RangeCheckpoint {
+    pub start: u64,
+    pub end: u64,
+    pub reached: u64,
+}
+
+impl Checkpoint {
+    pub fn is_complete(&self) -> bool {
+        self.ranges.iter().all(|range| range.reached == range.end)
+    }
+}
+
+impl Default for CheckpointFile {
+    fn default() -> Self {
+        CheckpointFile {
+            version: CHECKPOINT_VERSION,
+            searches: Vec::new(),
+        }
+    }
+}
+
+impl CheckpointFile {
+    // an empty file when there is nothing at the path yet.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(CheckpointFile::default());
+        }
+        let file: CheckpointFile = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if file.version != CHECKPOINT_VERSION {
+            return Err(format!(
+                "{}: unsupported checkpoint version {}",
+                path.display(),
+                file.version
+            )
+            .into());
+        }
+        Ok(file)
+    }
+
+    pub fn get(&self, fingerprint: u64) -> Option<&Checkpoint> {
+        self.searches
+            .iter()
+            .find(|checkpoint| checkpoint.fingerprint == fingerprint)
+    }
+
+    // replaces the search with the same fingerprint.
+    pub fn insert(&mut self, checkpoint: Checkpoint) {
+        match self
+            .searches
+            .iter_mut()
+            .find(|saved| saved.fingerprint == checkpoint.fingerprint)
+        {
+            Some(saved) => *saved = checkpoint,
+            None => self.searches.push(checkpoint),
+        }
+    }
+
+    pub fn remove(&mut self, fingerprint: u64) -> Option<Checkpoint> {
+        let index = self
+            .searches
+            .iter()
+            .position(|checkpoint| checkpoint.fingerprint == fingerprint)?;
+        Some(self.searches.remove(index))
+    }
+
+    // written next to the target first and then renamed, so an interrupted save never leaves
+    // a half-written checkpoint behind. without any searches the file is removed instead.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if self.searches.is_empty() {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        fs::write(&temporary, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+}
+
+// FNV-1a over the seeds and the rules of the chain, so that a checkpoint is not resumed
+// against a different almanac. unlike the std hasher this stays the same between builds.
+pub fn fingerprint(almanac: &Almanac, map_names: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut feed = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+    for range in &almanac.seeds {
+        feed(&range.start.to_le_bytes());
+        feed(&range.end.to_le_bytes());
+    }
+    for map_name in map_names {
+        feed(map_name.as_bytes());
+        for map in almanac.maps.get(*map_name).into_iter().flatten() {
+            feed(&map.destination_range_start.to_le_bytes());
+            feed(&map.source_range_start.to_le_bytes());
+            feed(&map.range_length.to_le_bytes());
+        }
+    }
+    hash
+}
+
+// run `work` while the checkpoints from `snapshot` are saved every `interval`, and once more at
+// the end. a failed save is reported on stderr but does not stop the work.
+pub fn save_periodically<T>(
+    path: &Path,
+    interval: Duration,
+    snapshot: impl Fn() -> CheckpointFile + Sync,
+    work: impl FnOnce() -> T,
+) -> Result<T, Box<dyn Error>> {
+    let finished = AtomicBool::new(false);
+    let result = thread::scope(|scope| {
+        let saver = scope.spawn(|| {
+            thread::park_timeout(interval);
+            while !finished.load(Ordering::Relaxed) {
+                if let Err(err) = snapshot().save(path) {
+                    eprintln!("warning: could not save checkpoint: {}", err);
+                }
+                thread::park_timeout(interval);
+            }
+        });
+        let result = work();
+        finished.store(true, Ordering::Relaxed);
+        saver.thread().unpark();
+        result
+    });
+    snapshot().save(path)?;
+    Ok(result)
+}
@@ -0,0 +1,232 @@
+use crate::ranges::owned_ranges;
+use crate::{Almanac, Map};
+use std::fmt;
+use std::ops::Range;
+
+// one piece of a piecewise-linear function: every value in `source` is moved so that
+// source.start lands on destination_start.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub source: Range<u64>,
+    pub destination_start: u64,
+}
+
+impl Segment {
+    pub fn offset(&self) -> i128 {
+        self.destination_start as i128 - self.source.start as i128
+    }
+
+    pub fn destination(&self) -> Range<u64> {
+        self.destination_start..self.destination_start + (self.source.end - self.source.start)
+    }
+}
+
+// a whole category (or a chain of categories) folded into sorted, non-overlapping segments
+// that cover 0..u64::MAX. identity stretches are kept as explicit segments with offset 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiecewiseMap {
+    segments: Vec<Segment>,
+}
+
+impl PiecewiseMap {
+    pub fn identity() -> Self {
+        PiecewiseMap {
+            segments: vec![Segment {
+                source: 0..u64::MAX,
+                destination_start: 0,
+            }],
+        }
+    }
+
+    // build the function of one category. overlapping rules are resolved the same way as
+    // map_value does it: the first rule in the list wins.
+    pub fn from_maps(maps: &[Map]) -> Self {
+        let mut segments = Vec::new();
+        let mut position = 0;
+        for (source, index) in owned_ranges(maps) {
+            if position < source.start {
+                segments.push(Segment {
+                    source: position..source.start,
+                    destination_start: position,
+                });
+            }
+            position = source.end;
+            let map = &maps[index];
+            segments.push(Segment {
+                destination_start: map.destination_range_start
+                    + (source.start - map.source_range_start),
+                source,
+            });
+        }
+        segments.push(Segment {
+            source: position..u64::MAX,
+            destination_start: position,
+        });
+        Self::normalized(segments)
+    }
+
+    // fold a chain of categories, e.g. "seed-to-soil" .. "humidity-to-location", into one function.
+    pub fn from_chain(almanac: &Almanac, map_names: &[&str]) -> Option<Self> {
+        let mut composed = Self::identity();
+        for map_name in map_names {
+            let maps = almanac.maps.get(*map_name)?;
+            composed = composed.then(&Self::from_maps(maps));
+        }
+        Some(composed)
+    }
+
+    // the function "first self, then next".
+    pub fn then(&self, next: &PiecewiseMap) -> PiecewiseMap {
+        let mut segments = Vec::new();
+        for segment in &self.segments {
+            let image = segment.destination();
+            let first = next.segment_index(image.start);
+            for next_segment in &next.segments[first..] {
+                if next_segment.source.start >= image.end {
+                    break;
+                }
+                let overlap = image.start.max(next_segment.source.start)
+                    ..image.end.min(next_segment.source.end);
+                let source_start = segment.source.start + (overlap.start - image.start);
+                segments.push(Segment {
+                    source: source_start..source_start + (overlap.end - overlap.start),
+                    destination_start: next_segment.destination_start
+                        + (overlap.start - next_segment.source.start),
+                });
+            }
+        }
+        Self::normalized(segments)
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    // one binary search instead of walking every category.
+    pub fn evaluate(&self, value: u64) -> u64 {
+        match self.segments.get(self.segment_index(value)) {
+            Some(segment) if segment.source.contains(&value) => {
+                segment.destination_start + (value - segment.source.start)
+            }
+            _ => value,
+        }
+    }
+
+    // smallest value produced by any of the given source ranges. every segment is increasing,
+    // so only the first value of each overlap needs to be looked at.
+    pub fn minimum_over(&self, ranges: &[Range<u64>]) -> Option<u64> {
+        let mut minimum = None;
+        for range in ranges {
+            if range.is_empty() {
+                continue;
+            }
+            let first = self.segment_index(range.start);
+            for segment in &self.segments[first..] {
+                if segment.source.start >= range.end {
+                    break;
+                }
+                let start = range.start.max(segment.source.start);
+                let value = segment.destination_start + (start - segment.source.start);
+                minimum = Some(minimum.map_or(value, |current: u64| current.min(value)));
+            }
+        }
+        minimum
+    }
+
+    fn segment_index(&self, value: u64) -> usize {
+        self.segments
+            .partition_point(|segment| segment.source.end <= value)
+    }
+
+    // sort by source and merge neighbours that shift by the same amount.
+    fn normalized(mut segments: Vec<Segment>) -> Self {
+        segments.retain(|segment| !segment.source.is_empty());
+        segments.sort_by_key(|segment| segment.source.start);
+
+        let mut merged: Vec<Segment> = Vec::with_capacity(segments.len());
+        for segment in segments {
+            match merged.last_mut() {
+                Some(last)
+                    if last.source.end == segment.source.start
+                        && last.offset() == segment.offset() =>
+                {
+                    last.source.end = segment.source.end;
+                }
+                _ => merged.push(segment),
+            }
+        }
+        PiecewiseMap { segments: merged }
+    }
+}
+
+impl fmt::Display for PiecewiseMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for segment in &self.segments {
+            writeln!(
+                f,
+                "[{}, {}) -> [{}, {}) offset {:+}",
+                segment.source.start,
+                segment.source.end,
+                segment.destination_start,
+                segment.destination().end,
+                segment.offset()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_value;
+
+    #[test]
+    fn test_compose() {
+        let first = vec![
+            Map {
+                destination_range_start: 50,
+                source_range_start: 98,
+                range_length: 2,
+            },
+            Map {
+                destination_range_start: 52,
+                source_range_start: 50,
+                range_length: 48,
+            },
+        ];
+        let second = vec![
+            Map {
+                destination_range_start: 0,
+                source_range_start: 15,
+                range_length: 37,
+            },
+            Map {
+                destination_range_start: 37,
+                source_range_start: 52,
+                range_length: 2,
+            },
+            Map {
+                destination_range_start: 39,
+                source_range_start: 0,
+                range_length: 15,
+            },
+        ];
+
+        let composed = PiecewiseMap::from_maps(&first).then(&PiecewiseMap::from_maps(&second));
+        for value in 0..200 {
+            assert_eq!(
+                composed.evaluate(value),
+                map_value(map_value(value, &first), &second)
+            );
+        }
+
+        // the segments must tile the whole domain without gaps.
+        let segments = composed.segments();
+        assert_eq!(segments.first().unwrap().source.start, 0);
+        assert_eq!(segments.last().unwrap().source.end, u64::MAX);
+        for pair in segments.windows(2) {
+            assert_eq!(pair[0].source.end, pair[1].source.start);
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use crate::ranges::{merge_ranges, owned_ranges};
+use crate::{Almanac, Map};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleCoverage {
+    // position of the rule in the category, as listed in the almanac.
+    pub index: usize,
+    pub rule: Map,
+    // how many seeds this rule moved on their way to the end of the chain.
+    pub seeds: u64,
+}
+
+impl RuleCoverage {
+    // a rule that no seed reaches, either because no value lands in its source range or
+    // because earlier rules cover all of it.
+    pub fn is_dead(&self) -> bool {
+        self.seeds == 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryCoverage {
+    pub map_name: String,
+    pub rules: Vec<RuleCoverage>,
+    // seeds that no rule covered, so they passed through unchanged.
+    pub passed_through: u64,
+}
+
+impl CategoryCoverage {
+    pub fn dead_rules(&self) -> impl Iterator<Item = &RuleCoverage> {
+        self.rules.iter().filter(|rule| rule.is_dead())
+    }
+}
+
+impl fmt::Display for CategoryCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {} of {} rules hit, {} seeds passed through",
+            self.map_name,
+            self.rules.len() - self.dead_rules().count(),
+            self.rules.len(),
+            self.passed_through
+        )?;
+        for rule in &self.rules {
+            write!(
+                f,
+                "  rule {} ({}): {} seeds",
+                rule.index, rule.rule, rule.seeds
+            )?;
+            if rule.is_dead() {
+                write!(f, ", dead")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// push the seed ranges through the chain and count, for every rule, how many seeds it moves.
+// the ranges are not merged between the categories: two seeds that meet on the same value are
+// still two seeds for the rules further down.
+pub fn rule_coverage(almanac: &Almanac, map_names: &[&str]) -> Option<Vec<CategoryCoverage>> {
+    let mut ranges = merge_ranges(almanac.seeds.clone());
+    let mut report = Vec::new();
+
+    for map_name in map_names {
+        let maps = almanac.maps.get(*map_name)?;
+        let owned = owned_ranges(maps);
+        let mut counts = vec![0; maps.len()];
+        let mut passed_through = 0;
+        let mut mapped = Vec::new();
+
+        for range in ranges {
+            let mut position = range.start;
+            let first = owned.partition_point(|(source, _)| source.end <= range.start);
+            for (source, index) in &owned[first..] {
+                if source.start >= range.end {
+                    break;
+                }
+                if position < source.start {
+                    passed_through += source.start - position;
+                    mapped.push(position..source.start);
+                }
+                let overlap = position.max(source.start)..range.end.min(source.end);
+                let rule = &maps[*index];
+                let start =
+                    rule.destination_range_start + (overlap.start - rule.source_range_start);
+                counts[*index] += overlap.end - overlap.start;
+                mapped.push(start..start + (overlap.end - overlap.start));
+                position = overlap.end;
+            }
+            if position < range.end {
+                passed_through += range.end - position;
+                mapped.push(position..range.end);
+            }
+        }
+
+        report.push(CategoryCoverage {
+            map_name: map_name.to_string(),
+            rules: maps
+                .iter()
+                .zip(counts)
+                .enumerate()
+                .map(|(index, (rule, seeds))| RuleCoverage {
+                    index,
+                    rule: rule.clone(),
+                    seeds,
+                })
+                .collect(),
+            passed_through,
+        });
+        ranges = mapped;
+    }
+    Some(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{generated_cases, GeneratorConfig};
+    use crate::trace::trace_seed;
+
+    #[test]
+    fn test_coverage_matches_traces() {
+        for case in generated_cases(300, GeneratorConfig::default()) {
+            let (almanac, map_names) = (&case.almanac, case.map_names());
+            let report = rule_coverage(almanac, &map_names).unwrap();
+
+            let mut expected: Vec<CategoryCoverage> = report
+                .iter()
+                .map(|category| CategoryCoverage {
+                    map_name: category.map_name.clone(),
+                    rules: category
+                        .rules
+                        .iter()
+                        .map(|rule| RuleCoverage {
+                            seeds: 0,
+                            ..rule.clone()
+                        })
+                        .collect(),
+                    passed_through: 0,
+                })
+                .collect();
+            for seed in merge_ranges(almanac.seeds.clone()).into_iter().flatten() {
+                let trace = trace_seed(almanac, &map_names, seed).unwrap();
+                for (category, step) in expected.iter_mut().zip(&trace.steps) {
+                    match &step.rule {
+                        Some(hit) => category.rules[hit.index].seeds += 1,
+                        None => category.passed_through += 1,
+                    }
+                }
+            }
+            assert_eq!(report, expected, "case {}", case.seed);
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use crate::compose::PiecewiseMap;
+use crate::Almanac;
+use std::fmt;
+use std::ops::Range;
+
+// source values that the old and the new function move by different amounts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangedInterval {
+    pub source: Range<u64>,
+    pub old_offset: i128,
+    pub new_offset: i128,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CategoryChange {
+    Added,
+    Removed,
+    // empty when the category still maps every value the same way, even if its rules were
+    // reordered, split or merged.
+    Changed(Vec<ChangedInterval>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryDiff {
+    pub map_name: String,
+    pub change: CategoryChange,
+}
+
+// the effect of the changes on one seed range, through the whole seed-to-location chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeedRangeDiff {
+    pub seeds: Range<u64>,
+    pub in_old: bool,
+    pub in_new: bool,
+    // None when the almanac has no chain from seed to location.
+    pub old_minimum: Option<u64>,
+    pub new_minimum: Option<u64>,
+    // seeds of the range that now end up on a different location.
+    pub moved_seeds: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlmanacDiff {
+    pub categories: Vec<CategoryDiff>,
+    pub seed_ranges: Vec<SeedRangeDiff>,
+}
+
+impl AlmanacDiff {
+    pub fn is_empty(&self) -> bool {
+        self.categories
+            .iter()
+            .all(|category| category.change == CategoryChange::Changed(Vec::new()))
+            && self
+                .seed_ranges
+                .iter()
+                .all(|range| range.in_old && range.in_new && range.moved_seeds == 0)
+    }
+}
+
+// where two functions disagree. both cover 0..u64::MAX, so their segments can be walked side
+// by side; neighbouring intervals with the same pair of offsets are reported as one.
+pub fn differences(old: &PiecewiseMap, new: &PiecewiseMap) -> Vec<ChangedInterval> {
+    let (old, new) = (old.segments(), new.segments());
+    let mut changed: Vec<ChangedInterval> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let mut position = 0;
+    while i < old.len() && j < new.len() {
+        let end = old[i].source.end.min(new[j].source.end);
+        let (old_offset, new_offset) = (old[i].offset(), new[j].offset());
+        if old_offset != new_offset {
+            match changed.last_mut() {
+                Some(last)
+                    if last.source.end == position
+                        && (last.old_offset, last.new_offset) == (old_offset, new_offset) =>
+                {
+                    last.source.end = end;
+                }
+                _ => changed.push(ChangedInterval {
+                    source: position..end,
+                    old_offset,
+                    new_offset,
+                }),
+            }
+        }
+        position = end;
+        if old[i].source.end == end {
+            i += 1;
+        }
+        if new[j].source.end == end {
+            j += 1;
+        }
+    }
+    changed
+}
+
+// compare two almanacs category by category, in the chain order of the old one with the
+// categories that only the new one has at the end. the seed ranges of both almanacs are
+// followed from seed to location.
+pub fn diff_almanacs(old: &Almanac, new: &Almanac) -> AlmanacDiff {
+    let mut map_names = old.map_names();
+    for map_name in new.map_names() {
+        if !map_names.contains(&map_name) {
+            map_names.push(map_name);
+        }
+    }
+
+    let categories = map_names
+        .into_iter()
+        .map(|map_name| {
+            let change = match (old.maps.get(&map_name), new.maps.get(&map_name)) {
+                (Some(old_maps), Some(new_maps)) => CategoryChange::Changed(differences(
+                    &PiecewiseMap::from_maps(old_maps),
+                    &PiecewiseMap::from_maps(new_maps),
+                )),
+                (None, _) => CategoryChange::Added,
+                (_, None) => CategoryChange::Removed,
+            };
+            CategoryDiff { map_name, change }
+        })
+        .collect();
+
+    let composed = |almanac: &Almanac| {
+        let chain = almanac.chain("seed", "location")?;
+        let map_names: Vec<&str> = chain.iter().map(String::as_str).collect();
+        PiecewiseMap::from_chain(almanac, &map_names)
+    };
+    let (old_function, new_function) = (composed(old), composed(new));
+    let moved = match (&old_function, &new_function) {
+        (Some(old_function), Some(new_function)) => differences(old_function, new_function),
+        _ => Vec::new(),
+    };
+
+    let mut seeds = old.seeds.clone();
+    for range in &new.seeds {
+        if !seeds.contains(range) {
+            seeds.push(range.clone());
+        }
+    }
+    let seed_ranges = seeds
+        .into_iter()
+        .map(|range| {
+            let moved_seeds = moved
+                .iter()
+                .map(|changed| {
+                    let overlap =
+                        changed.source.start.max(range.start)..changed.source.end.min(range.end);
+                    overlap.end.saturating_sub(overlap.start)
+                })
+                .sum();
+            SeedRangeDiff {
+                in_old: old.seeds.contains(&range),
+                in_new: new.seeds.contains(&range),
+                old_minimum: old_function
+                    .as_ref()
+                    .and_then(|function| function.minimum_over(std::slice::from_ref(&range))),
+                new_minimum: new_function
+                    .as_ref()
+                    .and_then(|function| function.minimum_over(std::slice::from_ref(&range))),
+                moved_seeds,
+                seeds: range,
+            }
+        })
+        .collect();
+
+    AlmanacDiff {
+        categories,
+        seed_ranges,
+    }
+}
+
+fn format_minimum(minimum: Option<u64>) -> String {
+    minimum.map_or("-".to_string(), |minimum| minimum.to_string())
+}
+
+impl fmt::Display for AlmanacDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for category in &self.categories {
+            match &category.change {
+                CategoryChange::Added => writeln!(f, "{}: added", category.map_name)?,
+                CategoryChange::Removed => writeln!(f, "{}: removed", category.map_name)?,
+                CategoryChange::Changed(changed) if changed.is_empty() => {
+                    writeln!(f, "{}: unchanged", category.map_name)?
+                }
+                CategoryChange::Changed(changed) => {
+                    writeln!(
+                        f,
+                        "{}: {} intervals changed",
+                        category.map_name,
+                        changed.len()
+                    )?;
+                    for interval in changed {
+                        writeln!(
+                            f,
+                            "  [{}, {}) offset {:+} -> {:+}",
+                            interval.source.start,
+                            interval.source.end,
+                            interval.old_offset,
+                            interval.new_offset
+                        )?;
+                    }
+                }
+            }
+        }
+        for range in &self.seed_ranges {
+            write!(f, "seeds [{}, {})", range.seeds.start, range.seeds.end)?;
+            match (range.in_old, range.in_new) {
+                (true, false) => write!(f, " (removed)")?,
+                (false, true) => write!(f, " (added)")?,
+                _ => {}
+            }
+            writeln!(
+                f,
+                ": minimum location {} -> {}, {} seeds moved",
+                format_minimum(range.old_minimum),
+                format_minimum(range.new_minimum),
+                range.moved_seeds
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{generate_almanac, generated_cases, GeneratorConfig};
+    use crate::map_value;
+    use crate::normalize::normalize_almanac;
+
+    #[test]
+    fn test_diff_matches_every_value() {
+        let config = GeneratorConfig {
+            categories: 4,
+            ..Default::default()
+        };
+        for case in generated_cases(100, config) {
+            let old = &case.almanac;
+            // the same seeds, with the rules of some categories swapped for others.
+            let mut new = generate_almanac(&case.config, case.seed + 1000);
+            new.seeds = old.seeds.clone();
+            new.maps.insert(
+                "seed-to-category1".to_string(),
+                old.maps["seed-to-category1"].clone(),
+            );
+
+            let diff = diff_almanacs(old, &new);
+            assert_eq!(diff.categories.len(), 3);
+            assert_eq!(
+                diff.categories[0].change,
+                CategoryChange::Changed(Vec::new())
+            );
+            for category in &diff.categories {
+                let CategoryChange::Changed(changed) = &category.change else {
+                    panic!("{} is in both almanacs", category.map_name);
+                };
+                let (old_maps, new_maps) =
+                    (&old.maps[&category.map_name], &new.maps[&category.map_name]);
+                for value in 0..case.config.value_limit + 10 {
+                    assert_eq!(
+                        changed
+                            .iter()
+                            .any(|interval| interval.source.contains(&value)),
+                        map_value(value, old_maps) != map_value(value, new_maps),
+                        "case {} {} value {}",
+                        case.seed,
+                        category.map_name,
+                        value
+                    );
+                }
+            }
+
+            let locate = |almanac: &Almanac, seed: u64| {
+                almanac.map_names().iter().fold(seed, |value, map_name| {
+                    map_value(value, &almanac.maps[map_name])
+                })
+            };
+            for range in &diff.seed_ranges {
+                let moved = range
+                    .seeds
+                    .clone()
+                    .filter(|&seed| locate(old, seed) != locate(&new, seed))
+                    .count();
+                assert_eq!(range.moved_seeds, moved as u64, "case {}", case.seed);
+                let minimum = range.seeds.clone().map(|seed| locate(&new, seed)).min();
+                assert_eq!(range.new_minimum, minimum, "case {}", case.seed);
+            }
+
+            // normalizing changes the rules but not the function.
+            assert!(diff_almanacs(old, &normalize_almanac(old).0).is_empty());
+        }
+    }
+}
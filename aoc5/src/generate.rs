@@ -0,0 +1,277 @@
+use crate::{Almanac, Map};
+use std::collections::HashMap;
+use std::ops::Range;
+
+// splitmix64: tiny, fast and good enough for reproducible test data.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // a value in the range, which must not be empty.
+    pub fn between(&mut self, range: Range<u64>) -> u64 {
+        range.start + self.next_u64() % (range.end - range.start)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratorConfig {
+    // number of categories in the chain, seed and location included.
+    pub categories: usize,
+    pub rules_per_map: Range<usize>,
+    // every seed and every rule stays below this value.
+    pub value_limit: u64,
+    pub seed_ranges: Range<usize>,
+    pub max_seed_range_length: u64,
+    // let the source ranges inside a category overlap, so that the rule order matters.
+    pub overlapping_rules: bool,
+}
+
+impl Default for GeneratorConfig {
+    // the shape of the puzzle input, scaled down so that every seed can still be checked.
+    fn default() -> Self {
+        GeneratorConfig {
+            categories: 8,
+            rules_per_map: 1..12,
+            value_limit: 1000,
+            seed_ranges: 1..5,
+            max_seed_range_length: 50,
+            overlapping_rules: false,
+        }
+    }
+}
+
+// "seed", "category1", "category2", ..., "location"
+pub fn category_names(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|index| match index {
+            0 => "seed".to_string(),
+            _ if index == count - 1 => "location".to_string(),
+            _ => format!("category{}", index),
+        })
+        .collect()
+}
+
+fn random_rules(config: &GeneratorConfig, rng: &mut Rng) -> Vec<Map> {
+    let limit = config.value_limit;
+    let count = rng.between(config.rules_per_map.start as u64..config.rules_per_map.end as u64);
+
+    let sources: Vec<Range<u64>> = if config.overlapping_rules {
+        (0..count)
+            .map(|_| {
+                let start = rng.between(0..limit);
+                start..rng.between(start..limit + 1)
+            })
+            .collect()
+    } else {
+        // pairs of sorted points give ranges that never overlap, with holes in between.
+        let mut points: Vec<u64> = (0..count * 2).map(|_| rng.between(0..limit + 1)).collect();
+        points.sort();
+        points.chunks(2).map(|pair| pair[0]..pair[1]).collect()
+    };
+
+    let mut rules: Vec<Map> = sources
+        .into_iter()
+        .map(|source| {
+            let length = source.end - source.start;
+            Map {
+                destination_range_start: rng.between(0..limit - length + 1),
+                source_range_start: source.start,
+                range_length: length,
+            }
+        })
+        .collect();
+    // the puzzle inputs are not sorted either.
+    for i in (1..rules.len()).rev() {
+        rules.swap(i, rng.between(0..i as u64 + 1) as usize);
+    }
+    rules
+}
+
+// a random almanac with a linear chain from "seed" to "location". the same config and seed
+// always give the same almanac.
+pub fn generate_almanac(config: &GeneratorConfig, seed: u64) -> Almanac {
+    assert!(config.categories >= 2 && config.value_limit >= 2);
+    let mut rng = Rng::new(seed);
+    let limit = config.value_limit;
+
+    let seed_count = rng.between(config.seed_ranges.start as u64..config.seed_ranges.end as u64);
+    let seeds = (0..seed_count)
+        .map(|_| {
+            let start = rng.between(0..limit - 1);
+            let longest = config.max_seed_range_length.min(limit - start);
+            start..start + rng.between(1..longest + 1)
+        })
+        .collect();
+
+    let names = category_names(config.categories);
+    let maps: HashMap<String, Vec<Map>> = names
+        .windows(2)
+        .map(|pair| {
+            (
+                format!("{}-to-{}", pair[0], pair[1]),
+                random_rules(config, &mut rng),
+            )
+        })
+        .collect();
+
+    Almanac { seeds, maps }
+}
+
+// the map names from seed to location, borrowed from the almanac's own keys so that the
+// tests can hand them on as they are.
+#[cfg(test)]
+pub(crate) fn location_chain(almanac: &Almanac) -> Vec<&str> {
+    let chain = almanac.chain("seed", "location").unwrap();
+    chain
+        .iter()
+        .map(|map_name| almanac.maps.get_key_value(map_name).unwrap().0.as_str())
+        .collect()
+}
+
+// one almanac of a generated test, with the seed and the config it was made from.
+#[cfg(test)]
+pub(crate) struct GeneratedCase {
+    pub seed: u64,
+    pub config: GeneratorConfig,
+    pub almanac: Almanac,
+}
+
+#[cfg(test)]
+impl GeneratedCase {
+    pub fn map_names(&self) -> Vec<&str> {
+        location_chain(&self.almanac)
+    }
+}
+
+// the almanacs for the seeds 0..count of `config`, every second one with overlapping rules so
+// that the rule order is exercised as well.
+#[cfg(test)]
+pub(crate) fn generated_cases(
+    count: u64,
+    config: GeneratorConfig,
+) -> impl Iterator<Item = GeneratedCase> {
+    (0..count).map(move |seed| {
+        let config = GeneratorConfig {
+            overlapping_rules: seed % 2 == 1,
+            ..config.clone()
+        };
+        GeneratedCase {
+            almanac: generate_almanac(&config, seed),
+            seed,
+            config,
+        }
+    })
+}
+
+// the differential tests: every solver has to agree with walking each seed through map_value.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compose::PiecewiseMap;
+    use crate::normalize::normalize_almanac;
+    use crate::ranges::{map_ranges, merge_ranges};
+    use crate::{brute_force_minimum, map_value, ranges, reverse};
+
+    fn naive_minimum(almanac: &Almanac, map_names: &[&str]) -> u64 {
+        almanac
+            .seeds
+            .iter()
+            .flat_map(|range| range.clone())
+            .map(|seed| {
+                map_names.iter().fold(seed, |value, map_name| {
+                    map_value(value, &almanac.maps[*map_name])
+                })
+            })
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_generate_is_reproducible() {
+        let config = GeneratorConfig::default();
+        assert_eq!(generate_almanac(&config, 7), generate_almanac(&config, 7));
+        assert_ne!(generate_almanac(&config, 7), generate_almanac(&config, 8));
+
+        let almanac = generate_almanac(&config, 7);
+        let chain = almanac.chain("seed", "location").unwrap();
+        assert_eq!(chain.len(), config.categories - 1);
+    }
+
+    #[test]
+    fn test_solvers_agree_on_generated_almanacs() {
+        // chains from a single map up to the seven of the puzzle.
+        let cases = (2..9).flat_map(|categories| {
+            generated_cases(
+                300,
+                GeneratorConfig {
+                    categories,
+                    ..Default::default()
+                },
+            )
+        });
+        for case in cases {
+            let (almanac, map_names) = (&case.almanac, case.map_names());
+            let name = format!("case {} with {} maps", case.seed, map_names.len());
+
+            let expected = naive_minimum(almanac, &map_names);
+            assert_eq!(
+                brute_force_minimum(almanac, &map_names),
+                Some(expected),
+                "brute force, {}",
+                name
+            );
+            assert_eq!(
+                ranges::minimum_location(almanac, &map_names),
+                Some(expected),
+                "ranges, {}",
+                name
+            );
+
+            let mut unindexed = merge_ranges(almanac.seeds.clone());
+            for map_name in &map_names {
+                unindexed = map_ranges(&unindexed, &almanac.maps[*map_name]);
+            }
+            assert_eq!(unindexed[0].start, expected, "map_ranges, {}", name);
+
+            let composed = PiecewiseMap::from_chain(almanac, &map_names).unwrap();
+            assert_eq!(
+                composed.minimum_over(&almanac.seeds),
+                Some(expected),
+                "composed, {}",
+                name
+            );
+
+            let (normalized, _) = normalize_almanac(almanac);
+            assert_eq!(
+                naive_minimum(&normalized, &map_names),
+                expected,
+                "normalized, {}",
+                name
+            );
+
+            // the location scan is the slowest, so it only gets a share of the cases.
+            if case.seed % 10 == 0 {
+                assert_eq!(
+                    reverse::minimum_location_by_reverse_scan(almanac, &map_names),
+                    Some(expected),
+                    "reverse scan, {}",
+                    name
+                );
+            }
+        }
+    }
+}
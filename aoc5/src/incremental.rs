@@ -0,0 +1,284 @@
+use crate::index::MapIndex;
+use crate::ranges::merge_ranges;
+use crate::{Almanac, Map};
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditError {
+    UnknownMap(String),
+    NoSuchRule { map_name: String, index: usize },
+    // a rule whose source or destination range would run past u64::MAX.
+    Overflow(Map),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EditError::UnknownMap(name) => write!(f, "no map '{}'", name),
+            EditError::NoSuchRule { map_name, index } => {
+                write!(f, "map '{}' has no rule {}", map_name, index)
+            }
+            EditError::Overflow(rule) => write!(f, "rule {} does not fit into 64 bits", rule),
+        }
+    }
+}
+
+impl Error for EditError {}
+
+// an almanac that is solved once and then kept up to date while its rules are edited. the
+// ranges that reach every category are cached, so an edit only recomputes the categories from
+// the edited one onwards, and stops early once a category hands on the same ranges as before.
+#[derive(Debug, Clone)]
+pub struct IncrementalSolver {
+    almanac: Almanac,
+    map_names: Vec<String>,
+    indexes: Vec<MapIndex>,
+    // stages[0] are the merged seeds, stages[i + 1] the ranges coming out of category i.
+    stages: Vec<Vec<Range<u64>>>,
+    // how many categories the last change had to run the ranges through again.
+    recomputed: usize,
+}
+
+impl IncrementalSolver {
+    pub fn new(almanac: Almanac, map_names: &[&str]) -> Option<Self> {
+        let indexes = map_names
+            .iter()
+            .map(|map_name| Some(MapIndex::new(almanac.maps.get(*map_name)?)))
+            .collect::<Option<Vec<_>>>()?;
+        let mut solver = IncrementalSolver {
+            stages: vec![merge_ranges(almanac.seeds.clone())],
+            almanac,
+            map_names: map_names.iter().map(|name| name.to_string()).collect(),
+            indexes,
+            recomputed: 0,
+        };
+        solver.recompute_from(0);
+        Some(solver)
+    }
+
+    pub fn almanac(&self) -> &Almanac {
+        &self.almanac
+    }
+
+    pub fn minimum(&self) -> Option<u64> {
+        self.locations().first().map(|range| range.start)
+    }
+
+    pub fn locations(&self) -> &[Range<u64>] {
+        self.stages.last().unwrap()
+    }
+
+    // the values that reach the given category of the chain, the seeds for the first one.
+    pub fn stage(&self, category: usize) -> &[Range<u64>] {
+        &self.stages[category]
+    }
+
+    pub fn recomputed(&self) -> usize {
+        self.recomputed
+    }
+
+    // the rule is inserted at `index`, or appended when there is no index.
+    pub fn add_rule(
+        &mut self,
+        map_name: &str,
+        index: Option<usize>,
+        rule: Map,
+    ) -> Result<(), EditError> {
+        check_rule(&rule)?;
+        self.edit(
+            map_name,
+            |maps| {
+                let index = index.unwrap_or(maps.len());
+                if index > maps.len() {
+                    return None;
+                }
+                maps.insert(index, rule);
+                Some(())
+            },
+            index.unwrap_or(0),
+        )
+    }
+
+    pub fn remove_rule(&mut self, map_name: &str, index: usize) -> Result<Map, EditError> {
+        self.edit(
+            map_name,
+            |maps| (index < maps.len()).then(|| maps.remove(index)),
+            index,
+        )
+    }
+
+    // returns the rule that was replaced.
+    pub fn replace_rule(
+        &mut self,
+        map_name: &str,
+        index: usize,
+        rule: Map,
+    ) -> Result<Map, EditError> {
+        check_rule(&rule)?;
+        self.edit(
+            map_name,
+            |maps| Some(std::mem::replace(maps.get_mut(index)?, rule)),
+            index,
+        )
+    }
+
+    pub fn set_seeds(&mut self, seeds: Vec<Range<u64>>) {
+        self.stages[0] = merge_ranges(seeds.clone());
+        self.almanac.seeds = seeds;
+        self.recompute_from(0);
+    }
+
+    fn edit<T>(
+        &mut self,
+        map_name: &str,
+        change: impl FnOnce(&mut Vec<Map>) -> Option<T>,
+        index: usize,
+    ) -> Result<T, EditError> {
+        let maps = self
+            .almanac
+            .maps
+            .get_mut(map_name)
+            .ok_or_else(|| EditError::UnknownMap(map_name.to_string()))?;
+        let result = change(maps).ok_or_else(|| EditError::NoSuchRule {
+            map_name: map_name.to_string(),
+            index,
+        })?;
+
+        // a map outside of the chain does not change the result.
+        self.recomputed = 0;
+        if let Some(category) = self.map_names.iter().position(|name| name == map_name) {
+            self.indexes[category] = MapIndex::new(maps);
+            self.recompute_from(category);
+        }
+        Ok(result)
+    }
+
+    fn recompute_from(&mut self, category: usize) {
+        // the old ranges after the category, to see whether the change still makes a difference.
+        let mut cached = self.stages.split_off(category + 1);
+        self.recomputed = 0;
+        for (offset, index) in self.indexes[category..].iter().enumerate() {
+            let ranges = index.map_ranges(self.stages.last().unwrap());
+            self.recomputed += 1;
+            let unchanged = cached.get(offset) == Some(&ranges);
+            self.stages.push(ranges);
+            if unchanged {
+                // everything further on sees the same input as before.
+                self.stages.extend(cached.drain(offset + 1..));
+                return;
+            }
+        }
+    }
+}
+
+fn check_rule(rule: &Map) -> Result<(), EditError> {
+    let fits = |start: u64| start.checked_add(rule.range_length).is_some();
+    if fits(rule.source_range_start) && fits(rule.destination_range_start) {
+        Ok(())
+    } else {
+        Err(EditError::Overflow(rule.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{
+        generate_almanac, generated_cases, location_chain, GeneratorConfig, Rng,
+    };
+    use crate::ranges;
+
+    #[test]
+    fn test_edits_match_full_solve() {
+        for case in generated_cases(50, GeneratorConfig::default()) {
+            let (config, map_names) = (&case.config, case.map_names());
+            let mut solver = IncrementalSolver::new(case.almanac.clone(), &map_names).unwrap();
+            let mut rng = Rng::new(case.seed);
+
+            for edit in 0..40 {
+                let category = rng.between(0..map_names.len() as u64) as usize;
+                let map_name = map_names[category];
+                let rules = solver.almanac().maps[map_name].len() as u64;
+                let start = rng.between(0..config.value_limit);
+                let rule = Map {
+                    destination_range_start: rng.between(0..config.value_limit),
+                    source_range_start: start,
+                    range_length: rng.between(0..config.value_limit - start + 1),
+                };
+                match rng.between(0..3) {
+                    0 => solver.add_rule(map_name, None, rule).unwrap(),
+                    1 if rules > 0 => {
+                        solver
+                            .remove_rule(map_name, rng.between(0..rules) as usize)
+                            .unwrap();
+                    }
+                    _ if rules > 0 => {
+                        let index = rng.between(0..rules) as usize;
+                        solver.replace_rule(map_name, index, rule).unwrap();
+                    }
+                    _ => solver.add_rule(map_name, Some(0), rule).unwrap(),
+                }
+                assert!(solver.recomputed() <= map_names.len() - category);
+
+                let mut expected = merge_ranges(solver.almanac().seeds.clone());
+                for map_name in &map_names {
+                    expected = ranges::map_ranges(&expected, &solver.almanac().maps[*map_name]);
+                }
+                assert_eq!(
+                    solver.locations(),
+                    expected,
+                    "case {} edit {}",
+                    case.seed,
+                    edit
+                );
+                assert_eq!(
+                    solver.minimum(),
+                    ranges::minimum_location(solver.almanac(), &map_names)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_edit_errors() {
+        let almanac = generate_almanac(&GeneratorConfig::default(), 1);
+        let map_names = location_chain(&almanac);
+        let mut solver = IncrementalSolver::new(almanac.clone(), &map_names).unwrap();
+        let rule = Map {
+            destination_range_start: 0,
+            source_range_start: 1,
+            range_length: u64::MAX,
+        };
+
+        assert_eq!(
+            solver.remove_rule("dirt-to-soil", 0),
+            Err(EditError::UnknownMap("dirt-to-soil".to_string()))
+        );
+        assert_eq!(
+            solver.remove_rule("seed-to-category1", 100),
+            Err(EditError::NoSuchRule {
+                map_name: "seed-to-category1".to_string(),
+                index: 100
+            })
+        );
+        assert_eq!(
+            solver.add_rule("seed-to-category1", None, rule.clone()),
+            Err(EditError::Overflow(rule))
+        );
+
+        // a rule that maps onto itself changes nothing, so only its own category is redone.
+        let identity = Map {
+            destination_range_start: 5,
+            source_range_start: 5,
+            range_length: 10,
+        };
+        let minimum = solver.minimum();
+        solver
+            .add_rule("seed-to-category1", Some(0), identity)
+            .unwrap();
+        assert_eq!(solver.minimum(), minimum);
+        assert_eq!(solver.recomputed(), 1);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::compose::PiecewiseMap;
+use crate::ranges::merge_ranges;
+use crate::Map;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    source: Range<u64>,
+    destination_start: u64,
+}
+
+// the rules of one category sorted by source_range_start, so a value can be looked up with a
+// binary search instead of scanning every rule. overlapping rules are resolved up front the
+// same way map_value does it (first rule wins), and rules that map onto themselves are left
+// out because the fallback does the same.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapIndex {
+    entries: Vec<Entry>,
+}
+
+impl MapIndex {
+    pub fn new(maps: &[Map]) -> Self {
+        let entries = PiecewiseMap::from_maps(maps)
+            .segments()
+            .iter()
+            .filter(|segment| segment.offset() != 0)
+            .map(|segment| Entry {
+                source: segment.source.clone(),
+                destination_start: segment.destination_start,
+            })
+            .collect();
+        MapIndex { entries }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // index of the first entry that ends after the value.
+    fn first_entry(&self, value: u64) -> usize {
+        self.entries
+            .partition_point(|entry| entry.source.end <= value)
+    }
+
+    pub fn lookup(&self, value: u64) -> u64 {
+        match self.entries.get(self.first_entry(value)) {
+            Some(entry) if entry.source.start <= value => {
+                entry.destination_start + (value - entry.source.start)
+            }
+            _ => value,
+        }
+    }
+
+    // same result as ranges::map_ranges, but only the entries that touch a range are visited.
+    pub fn map_ranges(&self, ranges: &[Range<u64>]) -> Vec<Range<u64>> {
+        let mut mapped = Vec::new();
+        for range in ranges {
+            let mut position = range.start;
+            for entry in &self.entries[self.first_entry(range.start)..] {
+                if entry.source.start >= range.end {
+                    break;
+                }
+                // the part in front of the entry passes through unchanged.
+                if position < entry.source.start {
+                    mapped.push(position..entry.source.start);
+                    position = entry.source.start;
+                }
+                let end = range.end.min(entry.source.end);
+                let destination_start = entry.destination_start + (position - entry.source.start);
+                mapped.push(destination_start..destination_start + (end - position));
+                position = end;
+            }
+            if position < range.end {
+                mapped.push(position..range.end);
+            }
+        }
+        merge_ranges(mapped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_value;
+    use crate::ranges::map_ranges;
+
+    #[test]
+    fn test_lookup_matches_linear_scan() {
+        let maps = vec![
+            Map {
+                destination_range_start: 49,
+                source_range_start: 53,
+                range_length: 8,
+            },
+            Map {
+                destination_range_start: 0,
+                source_range_start: 11,
+                range_length: 42,
+            },
+            Map {
+                destination_range_start: 42,
+                source_range_start: 0,
+                range_length: 7,
+            },
+            Map {
+                destination_range_start: 57,
+                source_range_start: 7,
+                range_length: 4,
+            },
+            // overlaps the first two rules, which have to win.
+            Map {
+                destination_range_start: 500,
+                source_range_start: 40,
+                range_length: 30,
+            },
+        ];
+        let index = MapIndex::new(&maps);
+        for value in 0..100 {
+            assert_eq!(index.lookup(value), map_value(value, &maps));
+        }
+
+        let ranges = vec![0..5, 9..45, 50..90];
+        assert_eq!(index.map_ranges(&ranges), map_ranges(&ranges, &maps));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::categories::split_map_name;
+use crate::{Almanac, Map};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::BufRead;
+
+// version of the schema below, bumped on incompatible changes.
+pub const JSON_VERSION: u32 = 1;
+
+// {
+//   "version": 1,
+//   "seeds": [{ "start": 79, "length": 14 }],
+//   "categories": ["seed", "soil", ...],
+//   "maps": [{
+//     "name": "seed-to-soil", "source": "seed", "destination": "soil",
+//     "rules": [{ "destination_range_start": 50, "source_range_start": 98, "range_length": 2 }]
+//   }, ...]
+// }
+// categories and maps are listed in chain order.
+#[derive(Debug, Serialize, Deserialize)]
+struct AlmanacJson {
+    version: u32,
+    seeds: Vec<SeedRangeJson>,
+    categories: Vec<String>,
+    maps: Vec<MapJson>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SeedRangeJson {
+    start: u64,
+    length: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MapJson {
+    name: String,
+    source: String,
+    destination: String,
+    rules: Vec<Map>,
+}
+
+// every category in the chain order of the maps, each one once.
+fn categories(map_names: &[String]) -> Vec<String> {
+    let mut categories: Vec<String> = Vec::new();
+    for (source, destination) in map_names.iter().filter_map(|name| split_map_name(name)) {
+        for category in [source, destination] {
+            if !categories.iter().any(|known| known == category) {
+                categories.push(category.to_string());
+            }
+        }
+    }
+    categories
+}
+
+pub fn almanac_to_json(almanac: &Almanac) -> String {
+    let map_names = almanac.map_names();
+    let categories = categories(&map_names);
+
+    let maps = map_names
+        .iter()
+        .filter_map(|name| {
+            let (source, destination) = split_map_name(name)?;
+            Some(MapJson {
+                name: name.clone(),
+                source: source.to_string(),
+                destination: destination.to_string(),
+                rules: almanac.maps[name].clone(),
+            })
+        })
+        .collect();
+
+    let json = AlmanacJson {
+        version: JSON_VERSION,
+        seeds: almanac
+            .seeds
+            .iter()
+            .map(|range| SeedRangeJson {
+                start: range.start,
+                length: range.end - range.start,
+            })
+            .collect(),
+        categories,
+        maps,
+    };
+    serde_json::to_string_pretty(&json).unwrap()
+}
+
+// the same checks as the text parser: consistent names, no duplicates, nothing past u64::MAX.
+// the categories are derived from the maps, so they have to agree with them.
+fn almanac_from(json: AlmanacJson) -> Result<Almanac, Box<dyn Error>> {
+    if json.version != JSON_VERSION {
+        return Err(format!("unsupported almanac version {}", json.version).into());
+    }
+
+    let mut seeds = Vec::new();
+    for seed in json.seeds {
+        let end = seed.start.checked_add(seed.length).ok_or_else(|| {
+            format!(
+                "seed range {} {} does not fit into 64 bits",
+                seed.start, seed.length
+            )
+        })?;
+        seeds.push(seed.start..end);
+    }
+
+    let mut maps = HashMap::new();
+    for map in json.maps {
+        if split_map_name(&map.name) != Some((map.source.as_str(), map.destination.as_str())) {
+            return Err(format!(
+                "map '{}' does not lead from '{}' to '{}'",
+                map.name, map.source, map.destination
+            )
+            .into());
+        }
+        for rule in &map.rules {
+            if rule
+                .source_range_start
+                .checked_add(rule.range_length)
+                .is_none()
+                || rule
+                    .destination_range_start
+                    .checked_add(rule.range_length)
+                    .is_none()
+            {
+                return Err(format!(
+                    "map '{}': rule {} does not fit into 64 bits",
+                    map.name, rule
+                )
+                .into());
+            }
+        }
+        if maps.insert(map.name.clone(), map.rules).is_some() {
+            return Err(format!("duplicate map '{}'", map.name).into());
+        }
+    }
+    let almanac = Almanac { seeds, maps };
+
+    let expected = categories(&almanac.map_names());
+    if json.categories != expected {
+        return Err(format!(
+            "categories [{}] do not match the maps, which lead through [{}]",
+            json.categories.join(", "),
+            expected.join(", ")
+        )
+        .into());
+    }
+    Ok(almanac)
+}
+
+pub fn almanac_from_json(json: &str) -> Result<Almanac, Box<dyn Error>> {
+    almanac_from(serde_json::from_str(json)?)
+}
+
+pub fn almanac_from_json_reader<R: BufRead>(reader: R) -> Result<Almanac, Box<dyn Error>> {
+    almanac_from(serde_json::from_reader(reader)?)
+}
+
+// JSON almanacs start with '{', the text format never does.
+pub fn looks_like_json<R: BufRead>(reader: &mut R) -> std::io::Result<bool> {
+    loop {
+        let buffer = reader.fill_buf()?;
+        match buffer.iter().position(|byte| !byte.is_ascii_whitespace()) {
+            Some(position) => return Ok(buffer[position] == b'{'),
+            None if buffer.is_empty() => return Ok(false),
+            None => {
+                let length = buffer.len();
+                reader.consume(length);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut maps = HashMap::new();
+        maps.insert(
+            "soil-to-water".to_string(),
+            vec![Map {
+                destination_range_start: 5,
+                source_range_start: 0,
+                range_length: 3,
+            }],
+        );
+        maps.insert("seed-to-soil".to_string(), Vec::new());
+        let almanac = Almanac {
+            seeds: vec![79..93, 55..56],
+            maps,
+        };
+
+        let json = almanac_to_json(&almanac);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["version"], 1);
+        assert_eq!(
+            value["categories"],
+            serde_json::json!(["seed", "soil", "water"])
+        );
+        assert_eq!(value["maps"][0]["name"], "seed-to-soil");
+        assert_eq!(value["maps"][1]["rules"][0]["range_length"], 3);
+        assert_eq!(
+            value["seeds"][0],
+            serde_json::json!({ "start": 79, "length": 14 })
+        );
+
+        assert_eq!(almanac_from_json(&json).unwrap(), almanac);
+        let mut reader = json.as_bytes();
+        assert!(looks_like_json(&mut reader).unwrap());
+        assert_eq!(almanac_from_json_reader(reader).unwrap(), almanac);
+
+        let broken = json.replace("\"source\": \"seed\"", "\"source\": \"dirt\"");
+        assert!(almanac_from_json(&broken).is_err());
+        let stale = json.replace("\"water\"\n", "\"fertilizer\"\n");
+        assert_ne!(stale, json);
+        assert!(almanac_from_json(&stale).is_err());
+        assert!(!looks_like_json(&mut "seeds: 1".as_bytes()).unwrap());
+    }
+}
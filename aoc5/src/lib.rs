@@ -0,0 +1,286 @@
+// a one-element list of ranges is a perfectly normal set of seeds or locations here.
+#![allow(clippy::single_range_in_vec_init)]
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+
+pub mod batch;
+pub mod categories;
+pub mod checkpoint;
+pub mod compose;
+pub mod coverage;
+pub mod diff;
+pub mod generate;
+pub mod incremental;
+pub mod index;
+pub mod json;
+pub mod normalize;
+pub mod parse;
+pub mod plot;
+pub mod progress;
+pub mod ranges;
+pub mod reverse;
+pub mod search;
+pub mod stats;
+pub mod trace;
+pub mod typed;
+pub mod validate;
+pub mod write;
+
+pub use parse::{
+    parse_almanac, parse_input, parse_reader, read_almanac, ParseError, ParseErrorKind,
+};
+pub use search::brute_force_minimum;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Map {
+    pub destination_range_start: u64,
+    pub source_range_start: u64,
+    pub range_length: u64,
+}
+
+impl Map {
+    // the rule length, cut short where the source or destination range would run past u64::MAX.
+    // the parser rejects such rules, so for a parsed almanac this is just range_length.
+    fn usable_length(&self) -> u64 {
+        self.range_length
+            .min(u64::MAX - self.source_range_start)
+            .min(u64::MAX - self.destination_range_start)
+    }
+
+    pub fn source_range(&self) -> Range<u64> {
+        self.source_range_start..self.source_range_start + self.usable_length()
+    }
+
+    pub fn destination_range(&self) -> Range<u64> {
+        self.destination_range_start..self.destination_range_start + self.usable_length()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Almanac {
+    pub seeds: Vec<Range<u64>>,
+    pub maps: HashMap<String, Vec<Map>>,
+}
+
+impl Almanac {
+    // the map names leading from one category to another, discovered from the map headers.
+    pub fn chain(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        categories::CategoryGraph::from_almanac(self).path(from, to)
+    }
+
+    // the routes from one category to another when the maps branch, shortest first, at most
+    // `limit` of them.
+    pub fn routes(&self, from: &str, to: &str, limit: usize) -> Vec<Vec<String>> {
+        categories::CategoryGraph::from_almanac(self).routes(from, to, limit)
+    }
+
+    // all map names in chain order, e.g. "seed-to-soil" first and "humidity-to-location" last.
+    pub fn map_names(&self) -> Vec<String> {
+        categories::CategoryGraph::from_almanac(self).ordered_map_names()
+    }
+}
+
+pub fn map_single_value(value: u64, map: &Map) -> Option<u64> {
+    if map.source_range().contains(&value) {
+        let offset = value - map.source_range_start;
+        Some(map.destination_range_start + offset)
+    } else {
+        None
+    }
+}
+
+pub fn map_value(value: u64, maps: &[Map]) -> u64 {
+    let mut destination = value;
+    for map in maps {
+        if let Some(new_destination) = map_single_value(destination, map) {
+            destination = new_destination;
+            break;
+        }
+    }
+    destination
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use compose::PiecewiseMap;
+
+    const EXAMPLE: &str = "seeds: 79 14 55 13
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4
+";
+
+    const MAP_NAMES: [&str; 7] = [
+        "seed-to-soil",
+        "soil-to-fertilizer",
+        "fertilizer-to-water",
+        "water-to-light",
+        "light-to-temperature",
+        "temperature-to-humidity",
+        "humidity-to-location",
+    ];
+
+    fn parse_example(seeds_are_ranges: bool) -> Almanac {
+        parse_almanac(EXAMPLE, "example", seeds_are_ranges).unwrap()
+    }
+
+    #[test]
+    fn test_range_solver_matches_brute_force() {
+        for (seeds_are_ranges, expected) in [(false, 35), (true, 46)] {
+            let mut almanac = parse_example(seeds_are_ranges);
+            let composed = PiecewiseMap::from_chain(&almanac, &MAP_NAMES).unwrap();
+            // an empty seed range starting at the lowest location holds no seeds.
+            let lowest = (0..120)
+                .min_by_key(|&seed| composed.evaluate(seed))
+                .unwrap();
+            assert!(composed.evaluate(lowest) < expected);
+            almanac.seeds.push(lowest..lowest);
+
+            assert_eq!(brute_force_minimum(&almanac, &MAP_NAMES), Some(expected));
+            assert_eq!(
+                ranges::minimum_location(&almanac, &MAP_NAMES),
+                Some(expected)
+            );
+
+            assert_eq!(composed.minimum_over(&almanac.seeds), Some(expected));
+            for seed in 0..120 {
+                let mut location = seed;
+                for map_name in MAP_NAMES {
+                    location = map_value(location, &almanac.maps[map_name]);
+                }
+                assert_eq!(composed.evaluate(seed), location);
+            }
+
+            assert_eq!(
+                reverse::minimum_location_by_reverse_scan(&almanac, &MAP_NAMES),
+                Some(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_branching_routes() {
+        // a shortcut from seed straight to water, next to the usual chain.
+        let text = format!("{}\nseed-to-water map:\n0 70 30\n", EXAMPLE);
+        let almanac = parse_almanac(&text, "example", false).unwrap();
+        let routes = ranges::minimum_per_route(&almanac, "seed", "location", 10);
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[0].map_names[0], "seed-to-water");
+        assert_eq!(routes[1].map_names, MAP_NAMES);
+        assert_eq!(routes[1].minimum, Some(35));
+        // seed 79 takes the shortcut to water 9, which ends at location 10.
+        assert_eq!(routes[0].minimum, Some(10));
+        assert_eq!(
+            trace::trace_seed(&almanac, &[&routes[0].map_names[0]], 79)
+                .unwrap()
+                .result(),
+            9
+        );
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        for seeds_are_ranges in [false, true] {
+            let almanac = parse_reader(EXAMPLE.as_bytes(), "example", seeds_are_ranges).unwrap();
+            assert_eq!(almanac, parse_example(seeds_are_ranges));
+        }
+    }
+
+    #[test]
+    fn test_chain_from_headers() {
+        let almanac = parse_example(false);
+        assert_eq!(almanac.chain("seed", "location").unwrap(), MAP_NAMES);
+        assert_eq!(almanac.chain("soil", "humidity").unwrap(), &MAP_NAMES[1..6]);
+        assert_eq!(almanac.chain("location", "seed"), None);
+        assert_eq!(almanac.map_names(), MAP_NAMES);
+    }
+
+    #[test]
+    fn test_seeds_for_locations() {
+        let almanac = parse_example(true);
+        // seed 82 is the one that reaches location 46.
+        let seeds = reverse::seeds_for_locations(&almanac, &MAP_NAMES, &[46..47]).unwrap();
+        assert!(seeds.iter().any(|range| range.contains(&82)));
+        for range in &seeds {
+            for seed in range.clone() {
+                let mut location = seed;
+                for map_name in MAP_NAMES {
+                    location = map_value(location, &almanac.maps[map_name]);
+                }
+                assert_eq!(location, 46);
+            }
+        }
+    }
+
+    #[test]
+    fn test_trace_seed() {
+        let almanac = parse_example(false);
+        let trace = trace::trace_seed(&almanac, &MAP_NAMES, 79).unwrap();
+        let values: Vec<u64> = trace.steps.iter().map(|step| step.output).collect();
+        assert_eq!(values, vec![81, 81, 81, 74, 78, 78, 82]);
+        assert_eq!(trace.result(), 82);
+
+        let rule = trace.steps[0].rule.as_ref().unwrap();
+        assert_eq!(
+            (rule.index, rule.source_range_start, rule.offset),
+            (1, 50, 2)
+        );
+        assert_eq!(trace.steps[1].rule, None);
+
+        let json: serde_json::Value = serde_json::from_str(&trace.to_json()).unwrap();
+        assert_eq!(json["steps"][3]["rule"]["offset"], -7);
+        assert!(json["steps"][1]["rule"].is_null());
+    }
+
+    #[test]
+    fn test_map_value() {
+        let maps = vec![
+            Map {
+                destination_range_start: 50,
+                source_range_start: 98,
+                range_length: 2,
+            },
+            Map {
+                destination_range_start: 52,
+                source_range_start: 50,
+                range_length: 48,
+            },
+        ];
+        assert_eq!(map_value(79, &maps), 81);
+        assert_eq!(map_value(14, &maps), 14);
+        assert_eq!(map_value(55, &maps), 57);
+        assert_eq!(map_value(13, &maps), 13);
+    }
+}
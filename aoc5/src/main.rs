@@ -1,224 +1,674 @@
-use rayon::prelude::*;
-use std::collections::HashMap;
+use aoc5::categories::route_to_string;
+use aoc5::checkpoint::{fingerprint, save_periodically, CheckpointFile};
+use aoc5::compose::PiecewiseMap;
+use aoc5::coverage::rule_coverage;
+use aoc5::diff::diff_almanacs;
+use aoc5::incremental::IncrementalSolver;
+use aoc5::json::almanac_to_json;
+use aoc5::normalize::normalize_almanac;
+use aoc5::plot::Plot;
+use aoc5::progress::Progress;
+use aoc5::search::{resume, seed_count, SearchState};
+use aoc5::stats::LocationStats;
+use aoc5::trace::trace_seed;
+use aoc5::validate::validate_almanac;
+use aoc5::write::write_almanac;
+use aoc5::{parse_input, ranges, read_almanac, reverse, Almanac};
 use std::error::Error;
 use std::fs;
-use std::ops::Range;
-use std::sync::mpsc::channel;
-
-#[derive(Debug)]
-struct Map {
-    destination_range_start: u64,
-    source_range_start: u64,
-    range_length: u32,
-}
+use std::io::{self, BufRead, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
-#[derive(Debug)]
-struct Almanac {
-    seeds: Vec<Range<u64>>,
-    maps: HashMap<String, Vec<Map>>,
-}
+// size of the --plot - output in characters.
+const ASCII_PLOT_WIDTH: usize = 72;
+const ASCII_PLOT_HEIGHT: usize = 20;
 
-impl std::str::FromStr for Map {
-    type Err = Box<dyn Error>;
+// how many routes through branching maps are solved at most.
+const MAX_ROUTES: usize = 64;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split_whitespace();
+// how often a running brute-force search saves its checkpoint.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
 
-        let destination_range_start = parts.next().unwrap().parse::<u64>()?;
-        let source_range_start = parts.next().unwrap().parse::<u64>()?;
-        let range_length = parts.next().unwrap().parse::<u32>()?;
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Solver {
+    BruteForce,
+    Ranges,
+    Composed,
+    Reverse,
+}
 
-        Ok(Map {
-            destination_range_start,
-            source_range_start,
-            range_length,
-        })
+struct Options {
+    solver: Solver,
+    from: String,
+    to: String,
+    // print the overlap and gap report of every category.
+    validate: bool,
+    // refuse almanacs with overlapping rules.
+    strict: bool,
+    // explain how this seed is mapped instead of solving.
+    trace: Option<u64>,
+    json: bool,
+    // merge and sort the rules of every category before doing anything else.
+    normalize: bool,
+    // write the almanac in this format instead of solving.
+    convert: Option<Format>,
+    // compare the almanac with this one instead of solving.
+    diff: Option<String>,
+    // which part to run, part 1 reads single seeds and part 2 seed ranges. both by default.
+    part: Option<u32>,
+    // give up on the brute-force search after this long and report the best location so far.
+    timeout: Option<Duration>,
+    // plot every category into this directory, or on stdout for "-", instead of solving.
+    plot: Option<String>,
+    // add the composed function of the whole chain to the plots.
+    plot_composed: bool,
+    // report how many seeds every rule moves instead of solving.
+    coverage: bool,
+    // keep the solution up to date while rules are edited from stdin.
+    edit: bool,
+    // print statistics about the reached locations, with this many of the smallest ones,
+    // instead of solving.
+    stats: Option<usize>,
+    // follow only this route when the maps branch, counted from 1 as in the solver output.
+    route: Option<usize>,
+    // resume the brute-force search from this file if it exists, and save its progress there.
+    checkpoint: Option<PathBuf>,
+    // set by Ctrl-C.
+    interrupted: Arc<AtomicBool>,
+    // set while a brute-force search runs, the only thing Ctrl-C stops cleanly.
+    searching: Arc<AtomicBool>,
+    // "-" stands for stdin.
+    files: Vec<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        // the per-seed search, the composed function and the location scan are available for
+        // cross-checking the range solver.
+        Options {
+            solver: Solver::Ranges,
+            // the chain of maps is looked up from the headers, by default from seed to location.
+            from: "seed".to_string(),
+            to: "location".to_string(),
+            validate: false,
+            strict: false,
+            trace: None,
+            json: false,
+            normalize: false,
+            convert: None,
+            diff: None,
+            part: None,
+            timeout: None,
+            plot: None,
+            plot_composed: false,
+            coverage: false,
+            edit: false,
+            stats: None,
+            route: None,
+            checkpoint: None,
+            interrupted: Arc::new(AtomicBool::new(false)),
+            searching: Arc::new(AtomicBool::new(false)),
+            files: Vec::new(),
+        }
     }
 }
 
-fn parse_input(
+// every category of the chain, and with --plot-composed the whole chain over the seeds, as
+// SVG files in the target directory or as ASCII on stdout when the target is "-".
+fn plot(
+    almanac: &Almanac,
+    map_names: &[&str],
     filename: &str,
-    map_names: &Vec<&str>,
-    seeds_are_ranges: bool,
-) -> Result<Almanac, Box<dyn Error>> {
-    let contents = fs::read_to_string(filename)?;
+    target: &str,
+    options: &Options,
+) -> Result<(), Box<dyn Error>> {
+    let mut plots: Vec<Plot> = map_names
+        .iter()
+        .map(|map_name| Plot::for_category(map_name, &almanac.maps[*map_name]))
+        .collect();
+    if options.plot_composed {
+        let function = PiecewiseMap::from_chain(almanac, map_names).ok_or("missing map")?;
+        let start = almanac.seeds.iter().map(|range| range.start).min();
+        let end = almanac.seeds.iter().map(|range| range.end).max();
+        if let (Some(start), Some(end)) = (start, end) {
+            let title = format!("{}-to-{}", options.from, options.to);
+            plots.push(Plot::for_function(&title, &function, start..end));
+        }
+    }
 
-    let mut almanac = Almanac {
-        seeds: Vec::new(),
-        maps: HashMap::new(),
+    if target == "-" {
+        for plot in plots {
+            println!("{}", plot.to_ascii(ASCII_PLOT_WIDTH, ASCII_PLOT_HEIGHT));
+        }
+        return Ok(());
+    }
+    fs::create_dir_all(target)?;
+    let stem = match filename {
+        "-" => "stdin".into(),
+        _ => Path::new(filename)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy(),
     };
+    for plot in plots {
+        let path = Path::new(target).join(format!("{}-{}.svg", stem, plot.title));
+        fs::write(&path, plot.to_svg())?;
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
 
-    let mut parsing_map = false;
-    let mut current_map = String::new();
-
-    for line in contents.lines() {
-        if parsing_map {
-            // if line is empty (after trimming whitespace), then stop parsing the map.
-            if line.trim().is_empty() {
-                parsing_map = false;
-            } else {
-                // if line starts with a number, then parse it into a map.
-                if let Ok(map) = line.parse::<Map>() {
-                    almanac.maps.get_mut(&current_map).unwrap().push(map);
-                }
-            }
-        } else {
-            // if line starts with "seeds:", then parse them into seeds.
-            // each seed is a number separated by spaces.
-            if line.starts_with("seeds:") {
-                if seeds_are_ranges {
-                    // the seeds are pair of numbers with start and length, separated by a space.
-                    // there can be multiple pairs. parse them into seeds:
-                    let seeds: Vec<&str> = line.split_whitespace().skip(1).collect();
-                    for pair in seeds.chunks(2) {
-                        let start = pair[0].parse::<u64>()?;
-                        let length = pair[1].parse::<u64>()?;
-                        almanac.seeds.push(start..start + length);
-                    }
-                } else {
-                    for seed in line.split_whitespace().skip(1) {
-                        let parsed_seed: u64 = seed.parse::<u64>()?;
-                        almanac.seeds.push(parsed_seed..parsed_seed + 1);
-                    }
-                }
-            } else {
-                // if the line starts with one of the map names, plus "map:" then start parsing the map.
-                for map_name in map_names {
-                    if line.starts_with(map_name) {
-                        parsing_map = true;
-                        almanac.maps.insert(map_name.to_string(), Vec::new());
-                        current_map = map_name.to_string();
-                    }
-                }
-            }
+// solve once, then read edit commands from stdin and print the new minimum after each one:
+//   add <map> <destination> <source> <length>
+//   insert <map> <index> <destination> <source> <length>
+//   set <map> <index> <destination> <source> <length>
+//   remove <map> <index>
+fn edit(almanac: Almanac, map_names: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut solver = IncrementalSolver::new(almanac, map_names).ok_or("missing map")?;
+    println!("minimum location: {:?}", solver.minimum());
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match edit_command(&mut solver, &line) {
+            Ok(()) => println!(
+                "minimum location: {:?} ({} categories recomputed)",
+                solver.minimum(),
+                solver.recomputed()
+            ),
+            Err(err) => eprintln!("error: {}", err),
         }
     }
-    Ok(almanac)
+    Ok(())
 }
 
-fn map_single_value(value: u64, map: &Map) -> Option<u64> {
-    if value >= map.source_range_start && value < map.source_range_start + map.range_length as u64 {
-        let offset = value - map.source_range_start;
-        Some(map.destination_range_start + offset)
-    } else {
-        None
+fn edit_command(solver: &mut IncrementalSolver, line: &str) -> Result<(), Box<dyn Error>> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        ["add", map_name, rule @ ..] => solver.add_rule(map_name, None, rule.join(" ").parse()?)?,
+        ["insert", map_name, index, rule @ ..] => {
+            solver.add_rule(map_name, Some(index.parse()?), rule.join(" ").parse()?)?
+        }
+        ["set", map_name, index, rule @ ..] => {
+            solver.replace_rule(map_name, index.parse()?, rule.join(" ").parse()?)?;
+        }
+        ["remove", map_name, index] => {
+            solver.remove_rule(map_name, index.parse()?)?;
+        }
+        _ => return Err(format!("unknown command '{}'", line).into()),
     }
+    Ok(())
 }
 
-fn map_value(value: u64, maps: &Vec<Map>) -> u64 {
-    let mut destination = value;
-    for map in maps {
-        if let Some(new_destination) = map_single_value(destination, map) {
-            destination = new_destination;
-            break;
-        }
+fn print_stats(filename: &str, stats: &LocationStats, count: usize) {
+    match (stats.minimum(), stats.maximum()) {
+        (Some(minimum), Some(maximum)) => println!(
+            "{} locations: minimum {}, maximum {}, {} distinct",
+            filename,
+            minimum,
+            maximum,
+            stats.distinct_count()
+        ),
+        _ => println!("{} locations: none", filename),
+    }
+    let ranges: Vec<String> = stats
+        .locations()
+        .iter()
+        .map(|range| format!("[{}, {})", range.start, range.end))
+        .collect();
+    println!("location ranges: {}", ranges.join(" "));
+    for (location, seeds) in stats.smallest(count) {
+        let seeds: Vec<String> = seeds.iter().map(u64::to_string).collect();
+        println!("{} <- seeds {}", location, seeds.join(", "));
     }
-    destination
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+// the per-seed search with a progress line, resumed from and saved to the checkpoint file when
+// one is given. the file keeps the searches of the other files, parts and routes next to this
+// one. None when the search was stopped before the end.
+fn brute_force(
+    almanac: &Almanac,
+    map_names: &[&str],
+    filename: &str,
+    options: &Options,
+) -> Result<Option<u64>, Box<dyn Error>> {
+    let key = fingerprint(almanac, map_names);
+    let mut saved = match &options.checkpoint {
+        Some(path) => CheckpointFile::load(path)?,
+        None => CheckpointFile::default(),
+    };
+    let state = match saved.remove(key) {
+        Some(checkpoint) => SearchState::from_checkpoint(almanac, map_names, &checkpoint)
+            .map_err(|err| format!("{}: {}", filename, err))?,
+        None => SearchState::new(almanac, map_names),
+    };
+    let mut progress = Progress::new(seed_count(almanac))
+        .with_done(state.done())
+        .with_cancel_flag(options.interrupted.clone());
+    if let Some(timeout) = options.timeout {
+        progress = progress.with_timeout(timeout);
+    }
 
-    #[test]
-    fn test_map_value() {
-        let maps = vec![
-            Map {
-                destination_range_start: 50,
-                source_range_start: 98,
-                range_length: 2,
+    let run = || progress.report(|| resume(almanac, map_names, &state, &progress));
+    options.searching.store(true, Ordering::Relaxed);
+    let outcome = match &options.checkpoint {
+        Some(path) => save_periodically(
+            path,
+            CHECKPOINT_INTERVAL,
+            || {
+                let mut file = saved.clone();
+                file.insert(state.checkpoint(&progress));
+                file
             },
-            Map {
-                destination_range_start: 52,
-                source_range_start: 50,
-                range_length: 48,
-            },
-        ];
-        assert_eq!(map_value(79, &maps), 81);
-        assert_eq!(map_value(14, &maps), 14);
-        assert_eq!(map_value(55, &maps), 57);
-        assert_eq!(map_value(13, &maps), 13);
+            run,
+        ),
+        None => Ok(run()),
+    };
+    options.searching.store(false, Ordering::Relaxed);
+    let outcome = outcome?.ok_or("missing map")?;
+
+    if !outcome.complete {
+        let best = outcome
+            .minimum
+            .ok_or("stopped before any seed was checked")?;
+        println!(
+            "{} stopped after {} of {} seeds, best destination so far: {}",
+            filename,
+            progress.done(),
+            progress.total(),
+            best
+        );
+        if let Some(path) = &options.checkpoint {
+            println!("progress saved to {}", path.display());
+        }
+        return Ok(None);
     }
+    // a finished search has nothing left to resume.
+    if let Some(path) = &options.checkpoint {
+        saved.save(path)?;
+    }
+    Ok(Some(outcome.minimum.ok_or("no seeds")?))
 }
 
 fn process_file(
     filename: &str,
-    map_names: &Vec<&str>,
     seeds_are_ranges: bool,
+    options: &Options,
+    stdin: Option<&[u8]>,
 ) -> Result<(), Box<dyn Error>> {
-    let almanac = parse_input(filename, &map_names, seeds_are_ranges)?;
+    let mut almanac = match stdin {
+        Some(contents) if filename == "-" => read_almanac(contents, "<stdin>", seeds_are_ranges)?,
+        _ => parse_input(filename, seeds_are_ranges)?,
+    };
+
+    if options.normalize {
+        let (normalized, reports) = normalize_almanac(&almanac);
+        // on stderr, so that a converted almanac on stdout stays clean.
+        for (map_name, report) in reports {
+            eprintln!("{}: {}", map_name, report);
+        }
+        almanac = normalized;
+    }
+
+    match options.convert {
+        Some(Format::Text) => {
+            return Ok(write_almanac(
+                io::stdout().lock(),
+                &almanac,
+                seeds_are_ranges,
+            )?);
+        }
+        Some(Format::Json) => {
+            println!("{}", almanac_to_json(&almanac));
+            return Ok(());
+        }
+        None => {}
+    }
+
+    if let Some(other) = &options.diff {
+        let new = parse_input(other, seeds_are_ranges)?;
+        println!("{} -> {}", filename, other);
+        print!("{}", diff_almanacs(&almanac, &new));
+        return Ok(());
+    }
+
+    let reports = validate_almanac(&almanac, options.strict)?;
+    if options.validate {
+        for report in reports {
+            print!("{}", report);
+        }
+    }
+
+    // when the maps branch, every route is solved on its own unless --route picks one. the
+    // other modes follow the picked route, or else the shortest one.
+    let (from, to) = (&options.from, &options.to);
+    let mut routes = almanac.routes(from, to, MAX_ROUTES + 1);
+    if routes.len() > MAX_ROUTES {
+        routes.pop();
+        eprintln!(
+            "note: {}: more than {} routes lead from {} to {}, only {} of them are used",
+            filename, MAX_ROUTES, from, to, MAX_ROUTES
+        );
+    }
+    let chain = match options.route {
+        Some(number) => {
+            let route = routes.get(number - 1).cloned().ok_or_else(|| {
+                format!(
+                    "{}: there is no route {} from {} to {}",
+                    filename, number, from, to
+                )
+            })?;
+            routes = vec![route.clone()];
+            route
+        }
+        None => almanac
+            .chain(from, to)
+            .ok_or_else(|| format!("{}: no maps lead from {} to {}", filename, from, to))?,
+    };
+    let map_names: &[&str] = &chain.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let single_route = options.plot.is_some()
+        || options.trace.is_some()
+        || options.coverage
+        || options.edit
+        || options.stats.is_some();
+    if single_route && routes.len() > 1 {
+        eprintln!(
+            "note: {}: {} routes lead from {} to {}, following the shortest one, {}. \
+             --route picks another one",
+            filename,
+            routes.len(),
+            from,
+            to,
+            route_to_string(&chain)
+        );
+    }
+
+    if let Some(target) = &options.plot {
+        return plot(&almanac, map_names, filename, target, options);
+    }
+
+    if let Some(seed) = options.trace {
+        let trace = trace_seed(&almanac, map_names, seed).ok_or("missing map")?;
+        if options.json {
+            println!("{}", trace.to_json());
+        } else {
+            print!("{}", trace);
+        }
+        return Ok(());
+    }
+
+    if options.coverage {
+        for category in rule_coverage(&almanac, map_names).ok_or("missing map")? {
+            print!("{}", category);
+        }
+        return Ok(());
+    }
+
+    if options.edit {
+        return edit(almanac, map_names);
+    }
+
+    if let Some(count) = options.stats {
+        let stats = LocationStats::from_chain(&almanac, map_names).ok_or("missing map")?;
+        print_stats(filename, &stats, count);
+        return Ok(());
+    }
 
     println!("seed count: {:?}", almanac.seeds.len());
-    let (sender, receiver) = channel();
-
-    almanac
-        .seeds
-        .into_par_iter()
-        .for_each_with(sender, |sender, seed_range| {
-            let mut smallest_destination = u64::MAX;
-            let mut counter = 0;
-            let total_seeds = seed_range.end - seed_range.start;
+
+    let mut minimum_destination: Option<u64> = None;
+    for (number, route) in routes.iter().enumerate() {
+        let map_names: Vec<&str> = route.iter().map(String::as_str).collect();
+        let Some(minimum) = solve(&almanac, &map_names, filename, options)? else {
+            return Ok(());
+        };
+        if routes.len() > 1 {
             println!(
-                "{}: will count {:?} seeds",
-                rayon::current_thread_index().unwrap(),
-                total_seeds
+                "{} route {} ({}): minimum destination {}",
+                filename,
+                number + 1,
+                route_to_string(route),
+                minimum
             );
-            for seed in seed_range {
-                let mut destination = seed;
-                for map_name in map_names {
-                    let maps = almanac.maps.get(&map_name.to_string()).unwrap();
+        }
+        minimum_destination = Some(minimum_destination.map_or(minimum, |best| best.min(minimum)));
+    }
 
-                    destination = map_value(destination, maps);
-                }
-                if destination < smallest_destination {
-                    smallest_destination = destination;
-                }
-                counter += 1;
+    println!(
+        "{} minimum destination: {}",
+        filename,
+        minimum_destination.ok_or("no route")?
+    );
 
-                if counter % 10000000 == 0 {
-                    println!(
-                        "{}: {:.2}%",
-                        rayon::current_thread_index().unwrap(),
-                        (counter as f64 / total_seeds as f64) * 100.0
-                    );
-                }
+    Ok(())
+}
+
+// the minimum over one route with the chosen solver, None when the brute-force search was
+// stopped before the end.
+fn solve(
+    almanac: &Almanac,
+    map_names: &[&str],
+    filename: &str,
+    options: &Options,
+) -> Result<Option<u64>, Box<dyn Error>> {
+    let minimum = match options.solver {
+        Solver::BruteForce => return brute_force(almanac, map_names, filename, options),
+        // split the seed ranges at the rule boundaries instead of visiting every seed.
+        Solver::Ranges => {
+            ranges::minimum_location(almanac, map_names).ok_or("no seeds or missing map")?
+        }
+        Solver::Composed => {
+            let composed = PiecewiseMap::from_chain(almanac, map_names).ok_or("missing map")?;
+            println!("seed-to-location:\n{}", composed);
+            composed.minimum_over(&almanac.seeds).ok_or("no seeds")?
+        }
+        Solver::Reverse => reverse::minimum_location_by_reverse_scan(almanac, map_names)
+            .ok_or("no seeds or missing map")?,
+    };
+    Ok(Some(minimum))
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut options = Options::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => options.from = args.next().ok_or("--from needs a category")?,
+            "--to" => options.to = args.next().ok_or("--to needs a category")?,
+            "--brute-force" => options.solver = Solver::BruteForce,
+            "--composed" => options.solver = Solver::Composed,
+            "--reverse" => options.solver = Solver::Reverse,
+            "--validate" => options.validate = true,
+            "--strict" => options.strict = true,
+            "--trace" => {
+                let seed = args.next().ok_or("--trace needs a seed")?;
+                options.trace = Some(seed.parse()?);
             }
-            println!(
-                "{}: sending {}",
-                rayon::current_thread_index().unwrap(),
-                smallest_destination
-            );
-            sender.send(smallest_destination).unwrap();
-        });
+            "--json" => options.json = true,
+            "--part" => match args.next().as_deref() {
+                Some("1") => options.part = Some(1),
+                Some("2") => options.part = Some(2),
+                _ => return Err("--part needs 1 or 2".into()),
+            },
+            "--normalize" => options.normalize = true,
+            "--convert" => match args.next().as_deref() {
+                Some("text") => options.convert = Some(Format::Text),
+                Some("json") => options.convert = Some(Format::Json),
+                _ => return Err("--convert needs text or json".into()),
+            },
+            "--timeout" => {
+                let seconds = args.next().ok_or("--timeout needs a number of seconds")?;
+                options.timeout = Some(Duration::from_secs_f64(seconds.parse()?));
+            }
+            "--diff" => options.diff = Some(args.next().ok_or("--diff needs a file")?),
+            "--plot" => options.plot = Some(args.next().ok_or("--plot needs a directory")?),
+            "--plot-composed" => options.plot_composed = true,
+            "--coverage" => options.coverage = true,
+            "--edit" => options.edit = true,
+            "--stats" => options.stats = Some(options.stats.unwrap_or(5)),
+            "--smallest" => {
+                let count = args.next().ok_or("--smallest needs a count")?;
+                options.stats = Some(count.parse()?);
+            }
+            "--route" => match args.next().map(|number| number.parse()) {
+                Some(Ok(number)) if number > 0 => options.route = Some(number),
+                _ => return Err("--route needs a number from 1".into()),
+            },
+            "--checkpoint" => {
+                let path = args.next().ok_or("--checkpoint needs a file")?;
+                options.checkpoint = Some(PathBuf::from(path));
+            }
+            "-" => options.files.push(arg),
+            _ if arg.starts_with('-') => return Err(format!("unknown argument: {}", arg).into()),
+            _ => options.files.push(arg),
+        }
+    }
+    if options.files.is_empty() {
+        options.files = vec!["input.txt".to_string(), "input_full.txt".to_string()];
+    }
 
-    let mut destinations: Vec<_> = receiver.iter().collect();
-    destinations.sort();
+    if options.solver != Solver::BruteForce
+        && (options.timeout.is_some() || options.checkpoint.is_some())
+    {
+        return Err("--timeout and --checkpoint only apply to --brute-force".into());
+    }
 
-    let minimum_destination = destinations[0];
+    if options.edit && options.files.iter().any(|file| file == "-") {
+        return Err(
+            "--edit reads its commands from stdin, the almanac has to come from a file".into(),
+        );
+    }
 
-    println!("{} minimum destination: {}", filename, minimum_destination);
+    // a conversion runs once per file, reading the seeds as ranges unless --part 1 is given.
+    let parts = match (options.part, options.convert) {
+        (Some(part), _) => vec![part],
+        (None, Some(_)) => vec![2],
+        (None, None) => vec![1, 2],
+    };
+
+    // stdin can only be read once, so it is kept in memory when it is needed more than once.
+    let stdin_reads = options.files.iter().filter(|file| *file == "-").count() * parts.len();
+    let stdin = if stdin_reads > 1 {
+        let mut contents = Vec::new();
+        io::stdin().read_to_end(&mut contents)?;
+        Some(contents)
+    } else {
+        None
+    };
+
+    // the first Ctrl-C during a brute-force search stops it cleanly, the files after it are
+    // skipped. anywhere else, or the second time, it ends the program as usual.
+    if options.solver == Solver::BruteForce {
+        let interrupted = options.interrupted.clone();
+        let searching = options.searching.clone();
+        ctrlc::set_handler(move || {
+            if !searching.load(Ordering::Relaxed) || interrupted.swap(true, Ordering::Relaxed) {
+                std::process::exit(130);
+            }
+        })?;
+    }
+
+    for &part in &parts {
+        for file in &options.files {
+            if options.interrupted.load(Ordering::Relaxed) {
+                return Err("interrupted".into());
+            }
+            process_file(file, part == 2, &options, stdin.as_deref())?;
+        }
+    }
 
     Ok(())
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let map_names = vec![
-        "seed-to-soil",
-        "soil-to-fertilizer",
-        "fertilizer-to-water",
-        "water-to-light",
-        "light-to-temperature",
-        "temperature-to-humidity",
-        "humidity-to-location",
-    ];
-
-    // Part 1
-    process_file("input.txt", &map_names, false)?;
-    process_file("input_full.txt", &map_names, false)?;
-
-    // Part 2
-    process_file("input.txt", &map_names, true)?;
-    process_file("input_full.txt", &map_names, true)?;
+// usage: aoc5 [options] [file ...], where "-" reads from stdin.
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc5::generate::{generate_almanac, GeneratorConfig};
+    use aoc5::search::SearchState;
+
+    #[test]
+    fn test_checkpoint_across_parts_and_routes() {
+        let directory = std::env::temp_dir().join(format!("aoc5-main-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let file = directory.join("almanac.txt");
+        let path = directory.join("checkpoint.json");
+        // a shortcut from seed straight to location next to the generated chain.
+        let mut generated = generate_almanac(&GeneratorConfig::default(), 4);
+        generated.maps.insert(
+            "seed-to-location".to_string(),
+            vec!["5 0 50".parse().unwrap()],
+        );
+        write_almanac(fs::File::create(&file).unwrap(), &generated, true).unwrap();
+        let filename = file.to_str().unwrap();
+        let options = Options {
+            solver: Solver::BruteForce,
+            checkpoint: Some(path.clone()),
+            ..Default::default()
+        };
+        let part = |seeds_are_ranges| {
+            let almanac = parse_input(filename, seeds_are_ranges).unwrap();
+            let routes = almanac.routes("seed", "location", MAX_ROUTES);
+            assert_eq!(routes.len(), 2);
+            (almanac, routes)
+        };
+
+        // an earlier run finished part 1 and the first route of part 2, and was stopped in the
+        // second route after its first seed range.
+        let (almanac, routes) = part(true);
+        let map_names: Vec<&str> = routes[1].iter().map(String::as_str).collect();
+        let mut checkpoint = SearchState::new(&almanac, &map_names).checkpoint(&Progress::new(0));
+        let first = &mut checkpoint.ranges[0];
+        first.reached = first.end;
+        let first_seeds = Almanac {
+            seeds: almanac.seeds[..1].to_vec(),
+            ..almanac.clone()
+        };
+        checkpoint.best = ranges::minimum_location(&first_seeds, &map_names);
+        let mut saved = CheckpointFile::default();
+        saved.insert(checkpoint.clone());
+        saved.save(&path).unwrap();
+
+        // the next run goes through both parts and both routes again, in the order of run()
+        // and process_file().
+        for seeds_are_ranges in [false, true] {
+            let (almanac, routes) = part(seeds_are_ranges);
+            for (number, route) in routes.iter().enumerate() {
+                let map_names: Vec<&str> = route.iter().map(String::as_str).collect();
+                let minimum = brute_force(&almanac, &map_names, filename, &options).unwrap();
+                assert_eq!(minimum, ranges::minimum_location(&almanac, &map_names));
+                if !seeds_are_ranges || number == 0 {
+                    // the searches before leave the saved one alone.
+                    assert_eq!(
+                        CheckpointFile::load(&path).unwrap().searches,
+                        [checkpoint.clone()]
+                    );
+                }
+            }
+        }
+        assert!(!path.exists());
+        fs::remove_dir_all(&directory).unwrap();
+    }
 }
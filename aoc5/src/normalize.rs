@@ -0,0 +1,153 @@
+use crate::ranges::owned_ranges;
+use crate::{Almanac, Map};
+use std::fmt;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NormalizeReport {
+    pub rules_before: usize,
+    pub rules_after: usize,
+    // rules with a length of zero.
+    pub dropped_empty: usize,
+    // identity rules added for the holes between the rules.
+    pub gaps_filled: usize,
+    // neighbouring rules that shift by the same amount and were merged into one.
+    pub collapsed: usize,
+}
+
+impl fmt::Display for NormalizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} rules ({} collapsed, {} empty dropped, {} gaps filled)",
+            self.rules_before,
+            self.rules_after,
+            self.collapsed,
+            self.dropped_empty,
+            self.gaps_filled
+        )
+    }
+}
+
+fn offset(map: &Map) -> i128 {
+    map.destination_range_start as i128 - map.source_range_start as i128
+}
+
+// the same function as `maps`, written as sorted rules without overlaps, with the holes between
+// the rules filled by explicit identity rules and with neighbouring rules that shift by the same
+// amount merged. values outside the first and last rule still pass through unchanged.
+pub fn normalize_maps(maps: &[Map]) -> (Vec<Map>, NormalizeReport) {
+    let mut report = NormalizeReport {
+        rules_before: maps.len(),
+        ..Default::default()
+    };
+
+    report.dropped_empty = maps
+        .iter()
+        .filter(|map| map.source_range().is_empty())
+        .count();
+    let pieces = owned_ranges(maps).into_iter().map(|(piece, index)| {
+        let map = &maps[index];
+        Map {
+            destination_range_start: map.destination_range_start
+                + (piece.start - map.source_range_start),
+            source_range_start: piece.start,
+            range_length: piece.end - piece.start,
+        }
+    });
+
+    let mut normalized: Vec<Map> = Vec::with_capacity(pieces.len());
+    for piece in pieces {
+        if let Some(last) = normalized.last() {
+            let gap_start = last.source_range().end;
+            if gap_start < piece.source_range_start {
+                report.gaps_filled += 1;
+                normalized.push(Map {
+                    destination_range_start: gap_start,
+                    source_range_start: gap_start,
+                    range_length: piece.source_range_start - gap_start,
+                });
+            }
+        }
+        match normalized.last_mut() {
+            Some(last)
+                if last.source_range().end == piece.source_range_start
+                    && offset(last) == offset(&piece) =>
+            {
+                last.range_length += piece.range_length;
+                report.collapsed += 1;
+            }
+            _ => normalized.push(piece),
+        }
+    }
+
+    report.rules_after = normalized.len();
+    (normalized, report)
+}
+
+// normalize every category. the reports are sorted by map name.
+pub fn normalize_almanac(almanac: &Almanac) -> (Almanac, Vec<(String, NormalizeReport)>) {
+    let mut normalized = Almanac {
+        seeds: almanac.seeds.clone(),
+        maps: Default::default(),
+    };
+    let mut reports = Vec::new();
+    for (map_name, maps) in &almanac.maps {
+        let (maps, report) = normalize_maps(maps);
+        normalized.maps.insert(map_name.clone(), maps);
+        reports.push((map_name.clone(), report));
+    }
+    reports.sort_by(|a, b| a.0.cmp(&b.0));
+    (normalized, reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_value;
+
+    fn rule(destination: u64, source: u64, length: u64) -> Map {
+        Map {
+            destination_range_start: destination,
+            source_range_start: source,
+            range_length: length,
+        }
+    }
+
+    #[test]
+    fn test_normalize_maps() {
+        let maps = vec![
+            rule(110, 10, 5),
+            // continues the first rule with the same shift.
+            rule(115, 15, 5),
+            rule(0, 40, 0),
+            // partly hidden behind the first rule.
+            rule(300, 5, 10),
+            rule(500, 30, 10),
+            // already an identity rule, merges with the gap in front of it.
+            rule(25, 25, 5),
+        ];
+        let (normalized, report) = normalize_maps(&maps);
+        assert_eq!(
+            normalized,
+            vec![
+                rule(300, 5, 5),
+                rule(110, 10, 10),
+                rule(20, 20, 10),
+                rule(500, 30, 10),
+            ]
+        );
+        assert_eq!(
+            report,
+            NormalizeReport {
+                rules_before: 6,
+                rules_after: 4,
+                dropped_empty: 1,
+                gaps_filled: 1,
+                collapsed: 2,
+            }
+        );
+        for value in 0..60 {
+            assert_eq!(map_value(value, &normalized), map_value(value, &maps));
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use crate::{categories, json, Almanac, Map};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    // name of the field that is missing, e.g. "range length".
+    MissingField(&'static str),
+    NonNumeric(String),
+    ExtraField(String),
+    // number of values on the seeds line.
+    OddSeedPairCount(usize),
+    RuleOutsideMap,
+    DuplicateMapHeader(String),
+    InvalidMapHeader(String),
+    UnexpectedLine(String),
+    // a range that would run past u64::MAX, e.g. "source range".
+    Overflow(&'static str),
+}
+
+// where and why the almanac could not be parsed. line and column are 1-based.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::MissingField(field) => write!(f, "missing {}", field),
+            ParseErrorKind::NonNumeric(token) => write!(f, "'{}' is not a number", token),
+            ParseErrorKind::ExtraField(token) => write!(f, "unexpected extra field '{}'", token),
+            ParseErrorKind::OddSeedPairCount(count) => write!(
+                f,
+                "seed ranges need start/length pairs, found {} values",
+                count
+            ),
+            ParseErrorKind::RuleOutsideMap => write!(f, "map rule outside of a map block"),
+            ParseErrorKind::DuplicateMapHeader(name) => write!(f, "duplicate map '{}'", name),
+            ParseErrorKind::InvalidMapHeader(name) => {
+                write!(
+                    f,
+                    "map header '{}' is not of the form <source>-to-<destination>",
+                    name
+                )
+            }
+            ParseErrorKind::UnexpectedLine(line) => write!(f, "unexpected line '{}'", line),
+            ParseErrorKind::Overflow(range) => write!(f, "{} does not fit into 64 bits", range),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.file, self.line, self.column, self.kind
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+impl ParseError {
+    fn new(column: usize, kind: ParseErrorKind) -> Self {
+        ParseError {
+            file: String::new(),
+            line: 1,
+            column,
+            kind,
+        }
+    }
+
+    fn at(self, file: &str, line: usize) -> Self {
+        ParseError {
+            file: file.to_string(),
+            line,
+            ..self
+        }
+    }
+}
+
+// the whitespace separated tokens of a line together with their 1-based columns.
+fn tokens(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.split_whitespace()
+        .map(move |token| (token.as_ptr() as usize - line.as_ptr() as usize + 1, token))
+}
+
+fn parse_number(column: usize, token: &str) -> Result<u64, ParseError> {
+    token
+        .parse::<u64>()
+        .map_err(|_| ParseError::new(column, ParseErrorKind::NonNumeric(token.to_string())))
+}
+
+impl std::str::FromStr for Map {
+    type Err = ParseError;
+
+    // errors point at line 1 of an unnamed file, parse_input fills in the real position.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = tokens(s);
+        let end_column = s.len() + 1;
+        let mut field = |name| {
+            let (column, token) = parts
+                .next()
+                .ok_or_else(|| ParseError::new(end_column, ParseErrorKind::MissingField(name)))?;
+            parse_number(column, token).map(|value| (column, value))
+        };
+
+        let (destination_column, destination_range_start) = field("destination range start")?;
+        let (source_column, source_range_start) = field("source range start")?;
+        let (_, range_length) = field("range length")?;
+
+        // the ranges are half-open, so an end of exactly u64::MAX is still fine.
+        if source_range_start.checked_add(range_length).is_none() {
+            return Err(ParseError::new(
+                source_column,
+                ParseErrorKind::Overflow("source range"),
+            ));
+        }
+        if destination_range_start.checked_add(range_length).is_none() {
+            return Err(ParseError::new(
+                destination_column,
+                ParseErrorKind::Overflow("destination range"),
+            ));
+        }
+
+        if let Some((column, token)) = parts.next() {
+            return Err(ParseError::new(
+                column,
+                ParseErrorKind::ExtraField(token.to_string()),
+            ));
+        }
+
+        Ok(Map {
+            destination_range_start,
+            source_range_start,
+            range_length,
+        })
+    }
+}
+
+// a file name of "-" reads the almanac from stdin.
+pub fn parse_input(filename: &str, seeds_are_ranges: bool) -> Result<Almanac, Box<dyn Error>> {
+    if filename == "-" {
+        return read_almanac(io::stdin().lock(), "<stdin>", seeds_are_ranges);
+    }
+    let file = File::open(filename).map_err(|err| format!("{}: {}", filename, err))?;
+    read_almanac(BufReader::new(file), filename, seeds_are_ranges)
+}
+
+// accepts both the text format and the JSON export. JSON almanacs store the seed ranges
+// explicitly, so seeds_are_ranges only applies to text.
+pub fn read_almanac<R: BufRead>(
+    mut reader: R,
+    file: &str,
+    seeds_are_ranges: bool,
+) -> Result<Almanac, Box<dyn Error>> {
+    if json::looks_like_json(&mut reader)? {
+        return json::almanac_from_json_reader(reader)
+            .map_err(|err| format!("{}: {}", file, err).into());
+    }
+    parse_reader(reader, file, seeds_are_ranges)
+}
+
+// parse line by line from anything that can be read, e.g. a file, stdin, a decompressor or an
+// in-memory buffer. `file` is only used in the error messages.
+pub fn parse_reader<R: BufRead>(
+    reader: R,
+    file: &str,
+    seeds_are_ranges: bool,
+) -> Result<Almanac, Box<dyn Error>> {
+    let mut parser = AlmanacParser::new(file, seeds_are_ranges);
+    for line in reader.lines() {
+        parser.parse_line(&line?)?;
+    }
+    Ok(parser.finish())
+}
+
+pub fn parse_almanac(
+    contents: &str,
+    file: &str,
+    seeds_are_ranges: bool,
+) -> Result<Almanac, ParseError> {
+    let mut parser = AlmanacParser::new(file, seeds_are_ranges);
+    for line in contents.lines() {
+        parser.parse_line(line)?;
+    }
+    Ok(parser.finish())
+}
+
+// the parsing state between two lines.
+struct AlmanacParser<'a> {
+    file: &'a str,
+    seeds_are_ranges: bool,
+    almanac: Almanac,
+    line_number: usize,
+    parsing_map: bool,
+    current_map: String,
+}
+
+impl<'a> AlmanacParser<'a> {
+    fn new(file: &'a str, seeds_are_ranges: bool) -> Self {
+        AlmanacParser {
+            file,
+            seeds_are_ranges,
+            almanac: Almanac {
+                seeds: Vec::new(),
+                maps: HashMap::new(),
+            },
+            line_number: 0,
+            parsing_map: false,
+            current_map: String::new(),
+        }
+    }
+
+    fn finish(self) -> Almanac {
+        self.almanac
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), ParseError> {
+        self.line_number += 1;
+        let (file, line_number) = (self.file, self.line_number);
+        let error = |column, kind| ParseError::new(column, kind).at(file, line_number);
+        let almanac = &mut self.almanac;
+
+        if self.parsing_map {
+            // if line is empty (after trimming whitespace), then stop parsing the map.
+            if line.trim().is_empty() {
+                self.parsing_map = false;
+            } else {
+                let map = line
+                    .parse::<Map>()
+                    .map_err(|err| err.at(file, line_number))?;
+                almanac.maps.get_mut(&self.current_map).unwrap().push(map);
+            }
+        } else if let Some(seeds) = line.strip_prefix("seeds:") {
+            // if line starts with "seeds:", then parse them into seeds.
+            // each seed is a number separated by spaces.
+            let offset = "seeds:".len();
+            let seeds: Vec<(usize, &str)> = tokens(seeds)
+                .map(|(column, token)| (column + offset, token))
+                .collect();
+            if self.seeds_are_ranges {
+                // the seeds are pair of numbers with start and length, separated by a space.
+                // there can be multiple pairs. parse them into seeds:
+                if !seeds.len().is_multiple_of(2) {
+                    let (column, _) = seeds[seeds.len() - 1];
+                    return Err(error(column, ParseErrorKind::OddSeedPairCount(seeds.len())));
+                }
+                for pair in seeds.chunks(2) {
+                    let start = parse_number(pair[0].0, pair[0].1)
+                        .map_err(|err| err.at(file, line_number))?;
+                    let length = parse_number(pair[1].0, pair[1].1)
+                        .map_err(|err| err.at(file, line_number))?;
+                    let end = start
+                        .checked_add(length)
+                        .ok_or_else(|| error(pair[0].0, ParseErrorKind::Overflow("seed range")))?;
+                    almanac.seeds.push(start..end);
+                }
+            } else {
+                for (column, seed) in seeds {
+                    let parsed_seed =
+                        parse_number(column, seed).map_err(|err| err.at(file, line_number))?;
+                    let end = parsed_seed
+                        .checked_add(1)
+                        .ok_or_else(|| error(column, ParseErrorKind::Overflow("seed")))?;
+                    almanac.seeds.push(parsed_seed..end);
+                }
+            }
+        } else if let Some(map_name) = line.trim().strip_suffix("map:") {
+            // if the line is a "<source>-to-<destination> map:" header, then start parsing the map.
+            let map_name = map_name.trim();
+            let column = line.len() - line.trim_start().len() + 1;
+            if categories::split_map_name(map_name).is_none() {
+                return Err(error(
+                    column,
+                    ParseErrorKind::InvalidMapHeader(map_name.to_string()),
+                ));
+            }
+            if almanac.maps.contains_key(map_name) {
+                return Err(error(
+                    column,
+                    ParseErrorKind::DuplicateMapHeader(map_name.to_string()),
+                ));
+            }
+            self.parsing_map = true;
+            almanac.maps.insert(map_name.to_string(), Vec::new());
+            self.current_map = map_name.to_string();
+        } else if let Some((column, token)) = tokens(line).next() {
+            let kind = if token.starts_with(|c: char| c.is_ascii_digit()) {
+                ParseErrorKind::RuleOutsideMap
+            } else {
+                ParseErrorKind::UnexpectedLine(line.trim().to_string())
+            };
+            return Err(error(column, kind));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(contents: &str, seeds_are_ranges: bool) -> ParseError {
+        parse_almanac(contents, "test.txt", seeds_are_ranges).unwrap_err()
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_error("seeds: 1 2\n\na-to-b map:\n1 2\n", false);
+        assert_eq!(
+            (err.line, err.column, err.kind.clone()),
+            (4, 4, ParseErrorKind::MissingField("range length"))
+        );
+        assert_eq!(err.to_string(), "test.txt:4:4: missing range length");
+
+        let err = parse_error("seeds: 1 2\n\na-to-b map:\n1 x2 3\n", false);
+        assert_eq!(
+            (err.line, err.column, err.kind),
+            (4, 3, ParseErrorKind::NonNumeric("x2".to_string()))
+        );
+
+        let err = parse_error("seeds: 1 2 3\n", true);
+        assert_eq!(
+            (err.line, err.column, err.kind),
+            (1, 12, ParseErrorKind::OddSeedPairCount(3))
+        );
+
+        let err = parse_error("seeds: 1 2\n\n1 2 3\n", false);
+        assert_eq!(
+            (err.line, err.column, err.kind),
+            (3, 1, ParseErrorKind::RuleOutsideMap)
+        );
+
+        let err = parse_error("a-to-b map:\n1 2 3\n\na-to-b map:\n", false);
+        assert_eq!(
+            (err.line, err.column, err.kind),
+            (
+                4,
+                1,
+                ParseErrorKind::DuplicateMapHeader("a-to-b".to_string())
+            )
+        );
+
+        let max = u64::MAX;
+        let err = parse_error(&format!("a-to-b map:\n0 {} 2\n", max - 1), false);
+        assert_eq!(
+            (err.line, err.column, err.kind),
+            (2, 3, ParseErrorKind::Overflow("source range"))
+        );
+        let err = parse_error(&format!("a-to-b map:\n{} 0 2\n", max), false);
+        assert_eq!(err.kind, ParseErrorKind::Overflow("destination range"));
+        let err = parse_error(&format!("seeds: {} 2\n", max - 1), true);
+        assert_eq!(
+            (err.column, err.kind),
+            (8, ParseErrorKind::Overflow("seed range"))
+        );
+        let err = parse_error(&format!("seeds: {}\n", max), false);
+        assert_eq!(err.kind, ParseErrorKind::Overflow("seed"));
+        // a range ending exactly at u64::MAX still fits.
+        let almanac = parse_almanac(
+            &format!("seeds: {} 1\n\na-to-b map:\n0 {} 1\n", max - 1, max - 1),
+            "test.txt",
+            true,
+        )
+        .unwrap();
+        assert_eq!(crate::map_value(max - 1, &almanac.maps["a-to-b"]), 0);
+
+        // the error survives the trip through a reader.
+        let err = parse_reader("seeds: 1 x\n".as_bytes(), "stdin", false).unwrap_err();
+        assert_eq!(err.to_string(), "stdin:1:10: 'x' is not a number");
+
+        let err = parse_error("seeds: 1\n\nab map:\n", false);
+        assert_eq!(err.kind, ParseErrorKind::InvalidMapHeader("ab".to_string()));
+    }
+}
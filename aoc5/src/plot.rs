@@ -0,0 +1,340 @@
+use crate::compose::PiecewiseMap;
+use crate::ranges::owned_ranges;
+use crate::Map;
+use std::fmt::Write;
+use std::ops::Range;
+
+const SVG_WIDTH: f64 = 720.0;
+const SVG_HEIGHT: f64 = 540.0;
+const SVG_MARGIN: f64 = 60.0;
+// one symbol per rule in the ASCII plot, reused once they run out.
+const SYMBOLS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+// a stretch of source values that is moved by one rule, or passed through unchanged when
+// `rule` is None.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlotPiece {
+    pub source: Range<u64>,
+    pub destination_start: u64,
+    pub rule: Option<usize>,
+}
+
+// a piecewise function ready to be drawn, restricted to the source values in `domain`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plot {
+    pub title: String,
+    // sorted, without holes, covering the domain.
+    pub pieces: Vec<PlotPiece>,
+    // one description per rule, used for the legend and the SVG tooltips.
+    pub labels: Vec<String>,
+    pub domain: Range<u64>,
+    // the destination values of the pieces.
+    pub range: Range<u64>,
+}
+
+impl Plot {
+    // one category: every rule in its own color over the span of all rules, with the holes
+    // between them drawn as identity.
+    pub fn for_category(map_name: &str, maps: &[Map]) -> Self {
+        let owned = owned_ranges(maps);
+        let domain = match (
+            owned.first(),
+            owned.iter().map(|(source, _)| source.end).max(),
+        ) {
+            (Some((first, _)), Some(end)) => first.start..end,
+            _ => 0..1,
+        };
+        let mut pieces = Vec::new();
+        let mut position = domain.start;
+        for (source, index) in owned {
+            if position < source.start {
+                pieces.push(PlotPiece {
+                    source: position..source.start,
+                    destination_start: position,
+                    rule: None,
+                });
+            }
+            let map = &maps[index];
+            position = source.end;
+            pieces.push(PlotPiece {
+                destination_start: map.destination_range_start
+                    + (source.start - map.source_range_start),
+                source,
+                rule: Some(index),
+            });
+        }
+        if position < domain.end {
+            pieces.push(PlotPiece {
+                source: position..domain.end,
+                destination_start: position,
+                rule: None,
+            });
+        }
+        let labels = maps
+            .iter()
+            .enumerate()
+            .map(|(index, map)| format!("rule {} ({})", index, map))
+            .collect();
+        Plot::new(map_name, pieces, labels, domain)
+    }
+
+    // a composed function over the given source values, one color per segment that moves
+    // its values.
+    pub fn for_function(title: &str, function: &PiecewiseMap, domain: Range<u64>) -> Self {
+        let mut pieces = Vec::new();
+        let mut labels = Vec::new();
+        for segment in function.segments() {
+            let source = segment.source.start.max(domain.start)..segment.source.end.min(domain.end);
+            if source.is_empty() {
+                continue;
+            }
+            let rule = if segment.offset() == 0 {
+                None
+            } else {
+                labels.push(format!("offset {:+}", segment.offset()));
+                Some(labels.len() - 1)
+            };
+            pieces.push(PlotPiece {
+                destination_start: segment.destination_start
+                    + (source.start - segment.source.start),
+                source,
+                rule,
+            });
+        }
+        Plot::new(title, pieces, labels, domain)
+    }
+
+    fn new(title: &str, pieces: Vec<PlotPiece>, labels: Vec<String>, domain: Range<u64>) -> Self {
+        let destinations = pieces.iter().map(|piece| {
+            piece.destination_start
+                ..piece.destination_start + (piece.source.end - piece.source.start)
+        });
+        let range = destinations
+            .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end))
+            .unwrap_or(0..1);
+        Plot {
+            title: title.to_string(),
+            pieces,
+            labels,
+            domain,
+            range,
+        }
+    }
+
+    fn label(&self, piece: &PlotPiece) -> String {
+        let destination_end = piece.destination_start + (piece.source.end - piece.source.start);
+        format!(
+            "{}: [{}, {}) -> [{}, {})",
+            piece.rule.map_or("identity", |rule| &self.labels[rule]),
+            piece.source.start,
+            piece.source.end,
+            piece.destination_start,
+            destination_end
+        )
+    }
+
+    // a standalone SVG document. identity pieces are drawn dashed and gray.
+    pub fn to_svg(&self) -> String {
+        let (left, top) = (SVG_MARGIN, SVG_MARGIN);
+        let (right, bottom) = (SVG_WIDTH - SVG_MARGIN, SVG_HEIGHT - SVG_MARGIN);
+        let x = |value: u64| left + scale(value, &self.domain) * (right - left);
+        let y = |value: u64| bottom - scale(value, &self.range) * (bottom - top);
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="monospace" font-size="12">"#,
+            w = SVG_WIDTH,
+            h = SVG_HEIGHT
+        )
+        .unwrap();
+        writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+        writeln!(
+            svg,
+            r#"<text x="{}" y="30" font-size="16">{}</text>"#,
+            left,
+            escape(&self.title)
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<path d="M {left} {top} V {bottom} H {right}" fill="none" stroke="black"/>"#
+        )
+        .unwrap();
+        for (text, x, y, anchor) in [
+            (self.domain.start, left, bottom + 20.0, "start"),
+            (self.domain.end, right, bottom + 20.0, "end"),
+            (self.range.start, left - 6.0, bottom, "end"),
+            (self.range.end, left - 6.0, top + 10.0, "end"),
+        ] {
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{}" text-anchor="{}">{}</text>"#,
+                x, y, anchor, text
+            )
+            .unwrap();
+        }
+        writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="middle">source</text>"#,
+            (left + right) / 2.0,
+            bottom + 40.0
+        )
+        .unwrap();
+
+        for piece in &self.pieces {
+            let destination_end = piece.destination_start + (piece.source.end - piece.source.start);
+            let style = match piece.rule {
+                Some(rule) => format!(r#"stroke="{}" stroke-width="3""#, color(rule)),
+                None => r##"stroke="#999" stroke-width="1.5" stroke-dasharray="4 3""##.to_string(),
+            };
+            writeln!(
+                svg,
+                r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" {}><title>{}</title></line>"#,
+                x(piece.source.start),
+                y(piece.destination_start),
+                x(piece.source.end),
+                y(destination_end),
+                style,
+                escape(&self.label(piece))
+            )
+            .unwrap();
+        }
+        writeln!(svg, "</svg>").unwrap();
+        svg
+    }
+
+    // the same picture in characters: every column shows where its source value lands, drawn
+    // with the symbol of the rule that moves it, or '.' for identity.
+    pub fn to_ascii(&self, width: usize, height: usize) -> String {
+        let mut grid = vec![vec![b' '; width]; height];
+        let span = (self.domain.end - self.domain.start) as f64;
+        let columns = (0..width).map(|column| {
+            let value = self.domain.start + ((column as f64 + 0.5) / width as f64 * span) as u64;
+            let index = self
+                .pieces
+                .partition_point(|piece| piece.source.end <= value);
+            self.pieces
+                .get(index)
+                .filter(|piece| piece.source.contains(&value))
+                .map(|piece| {
+                    (
+                        piece,
+                        piece.destination_start + (value - piece.source.start),
+                    )
+                })
+        });
+        for (column, point) in columns.enumerate() {
+            let Some((piece, destination)) = point else {
+                continue;
+            };
+            let row = ((scale(destination, &self.range) * height as f64) as usize).min(height - 1);
+            grid[height - 1 - row][column] = match piece.rule {
+                Some(rule) => SYMBOLS[rule % SYMBOLS.len()],
+                None => b'.',
+            };
+        }
+
+        let mut text = String::new();
+        writeln!(text, "{}", self.title).unwrap();
+        writeln!(text, "{}", self.range.end).unwrap();
+        for row in grid {
+            writeln!(text, "|{}", String::from_utf8(row).unwrap().trim_end()).unwrap();
+        }
+        writeln!(text, "+{}", "-".repeat(width)).unwrap();
+        let start = format!("{} (destination {})", self.domain.start, self.range.start);
+        let end = self.domain.end.to_string();
+        writeln!(
+            text,
+            "{}{:>pad$}",
+            start,
+            end,
+            pad = (width + 1).saturating_sub(start.len())
+        )
+        .unwrap();
+        for (rule, label) in self.labels.iter().enumerate() {
+            writeln!(
+                text,
+                "  {} {}",
+                SYMBOLS[rule % SYMBOLS.len()] as char,
+                label
+            )
+            .unwrap();
+        }
+        text
+    }
+}
+
+// position of the value in the range, from 0.0 to 1.0.
+fn scale(value: u64, range: &Range<u64>) -> f64 {
+    (value - range.start) as f64 / (range.end - range.start).max(1) as f64
+}
+
+// well spread hues, so that neighbouring rules are easy to tell apart.
+fn color(rule: usize) -> String {
+    format!("hsl({}, 70%, 42%)", rule * 137 % 360)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plot_category() {
+        let maps = vec![
+            Map {
+                destination_range_start: 50,
+                source_range_start: 98,
+                range_length: 2,
+            },
+            Map {
+                destination_range_start: 52,
+                source_range_start: 50,
+                range_length: 48,
+            },
+        ];
+        let plot = Plot::for_category("seed-to-soil", &maps);
+        assert_eq!(plot.domain, 50..100);
+        assert_eq!(plot.range, 50..100);
+        assert_eq!(
+            plot.pieces,
+            vec![
+                PlotPiece {
+                    source: 50..98,
+                    destination_start: 52,
+                    rule: Some(1),
+                },
+                PlotPiece {
+                    source: 98..100,
+                    destination_start: 50,
+                    rule: Some(0),
+                },
+            ]
+        );
+
+        let svg = plot.to_svg();
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<line").count(), 2);
+        assert!(svg.contains("rule 0 (50 98 2): [98, 100) -&gt; [50, 52)"));
+
+        let ascii = plot.to_ascii(50, 10);
+        let rows: Vec<&str> = ascii.lines().collect();
+        // rule 1 climbs from the bottom left, rule 0 drops back down in the last two columns.
+        assert_eq!(rows[11], format!("|111{}00", " ".repeat(45)));
+        assert!(rows[2].ends_with('1'));
+        assert!(ascii.contains("  1 rule 1 (52 50 48)"));
+
+        // the identity between two rules is drawn as well.
+        let composed = PiecewiseMap::from_maps(&maps);
+        let plot = Plot::for_function("seed-to-soil", &composed, 40..100);
+        assert_eq!(plot.pieces[0].rule, None);
+        assert_eq!(plot.pieces[0].source, 40..50);
+        assert!(plot.to_ascii(60, 10).contains('.'));
+    }
+}
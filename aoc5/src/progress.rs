@@ -0,0 +1,194 @@
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const BAR_WIDTH: usize = 30;
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+
+// shared by all workers of a search: how many seeds are done, the best location so far and
+// whether the search should stop. the workers only touch atomics, so they never wait on each
+// other or on the reporter.
+#[derive(Debug)]
+pub struct Progress {
+    total: u64,
+    done: AtomicU64,
+    // seeds that were already done when the progress was created, e.g. by a resumed search.
+    // they do not count towards the throughput.
+    initial: u64,
+    best: AtomicU64,
+    // shared with the Ctrl-C handler.
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+    started: Instant,
+}
+
+impl Progress {
+    pub fn new(total: u64) -> Self {
+        Progress {
+            total,
+            done: AtomicU64::new(0),
+            initial: 0,
+            best: AtomicU64::new(u64::MAX),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            deadline: None,
+            started: Instant::now(),
+        }
+    }
+
+    // stop when this flag is set, e.g. from a signal handler.
+    pub fn with_cancel_flag(mut self, cancelled: Arc<AtomicBool>) -> Self {
+        self.cancelled = cancelled;
+        self
+    }
+
+    pub fn with_done(mut self, done: u64) -> Self {
+        self.done = AtomicU64::new(done);
+        self.initial = done;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Some(self.started + timeout);
+        self
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn done(&self) -> u64 {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    pub fn add(&self, seeds: u64) {
+        self.done.fetch_add(seeds, Ordering::Relaxed);
+    }
+
+    // a location some seed reaches; only the smallest one is kept.
+    pub fn offer(&self, location: u64) {
+        self.best.fetch_min(location, Ordering::Relaxed);
+    }
+
+    pub fn best(&self) -> Option<u64> {
+        match self.best.load(Ordering::Relaxed) {
+            u64::MAX if self.done() == 0 => None,
+            best => Some(best),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    // set by cancel, by the shared flag or once the timeout has passed.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    // "[#######.......]  23.4%  112.5 Mseeds/s  ETA 1m 05s  best 46"
+    pub fn render(&self) -> String {
+        let done = self.done();
+        // the total can be short when it was capped at u64::MAX.
+        let fraction = if self.total == 0 {
+            1.0
+        } else {
+            (done as f64 / self.total as f64).min(1.0)
+        };
+        let filled = (fraction * BAR_WIDTH as f64) as usize;
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let rate = if elapsed > 0.0 {
+            (done - self.initial) as f64 / elapsed
+        } else {
+            0.0
+        };
+        let eta = if rate > 0.0 {
+            format_duration(self.total.saturating_sub(done) as f64 / rate)
+        } else {
+            "?".to_string()
+        };
+        let best = match self.best() {
+            Some(best) => best.to_string(),
+            None => "-".to_string(),
+        };
+        format!(
+            "[{}{}] {:5.1}%  {:.1} Mseeds/s  ETA {}  best {}",
+            "#".repeat(filled),
+            ".".repeat(BAR_WIDTH - filled),
+            fraction * 100.0,
+            rate / 1e6,
+            eta,
+            best
+        )
+    }
+
+    // run `work` while one progress line on stderr is redrawn in place. nothing is drawn when
+    // stderr is not a terminal.
+    pub fn report<T>(&self, work: impl FnOnce() -> T) -> T {
+        if !io::stderr().is_terminal() {
+            return work();
+        }
+        let finished = AtomicBool::new(false);
+        thread::scope(|scope| {
+            let reporter = scope.spawn(|| {
+                while !finished.load(Ordering::Relaxed) {
+                    eprint!("\r{}", self.render());
+                    io::stderr().flush().ok();
+                    thread::park_timeout(REDRAW_INTERVAL);
+                }
+                eprintln!("\r{}", self.render());
+            });
+            let result = work();
+            finished.store(true, Ordering::Relaxed);
+            reporter.thread().unpark();
+            result
+        })
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {:02}m", seconds / 3600, seconds / 60 % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress() {
+        let progress = Progress::new(200);
+        assert_eq!(progress.best(), None);
+        progress.add(50);
+        progress.offer(70);
+        progress.offer(46);
+        progress.offer(90);
+        assert_eq!(progress.best(), Some(46));
+        assert!(progress
+            .render()
+            .starts_with("[#######.......................]  25.0%"));
+        assert!(progress.render().ends_with("best 46"));
+
+        assert!(!progress.is_cancelled());
+        progress.cancel();
+        assert!(progress.is_cancelled());
+        assert!(Progress::new(1).with_timeout(Duration::ZERO).is_cancelled());
+
+        // more seeds done than the capped total.
+        let progress = Progress::new(10);
+        progress.add(20);
+        assert!(progress.render().contains("100.0%  "));
+
+        assert_eq!(format_duration(5.2), "5s");
+        assert_eq!(format_duration(65.0), "1m 05s");
+        assert_eq!(format_duration(7260.0), "2h 01m");
+    }
+}
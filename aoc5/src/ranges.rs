@@ -0,0 +1,177 @@
+use crate::index::MapIndex;
+use crate::{Almanac, Map};
+use std::ops::Range;
+
+// sort the ranges and merge the ones that overlap or touch. empty ranges are dropped.
+pub fn merge_ranges(mut ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    ranges.retain(|range| !range.is_empty());
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+// the parts of `range` that are not in the sorted, merged `covered` ranges.
+fn subtract(range: Range<u64>, covered: &[Range<u64>]) -> Vec<Range<u64>> {
+    let mut pieces = Vec::new();
+    let mut position = range.start;
+    for other in covered {
+        if other.end <= position {
+            continue;
+        }
+        if other.start >= range.end {
+            break;
+        }
+        if position < other.start {
+            pieces.push(position..other.start);
+        }
+        position = position.max(other.end);
+    }
+    if position < range.end {
+        pieces.push(position..range.end);
+    }
+    pieces
+}
+
+// the part of the source values that each rule really handles, sorted by start. overlaps are
+// resolved like map_value does it: the first rule that covers a value owns it.
+pub(crate) fn owned_ranges(maps: &[Map]) -> Vec<(Range<u64>, usize)> {
+    let mut owned = Vec::new();
+    let mut covered: Vec<Range<u64>> = Vec::new();
+    for (index, map) in maps.iter().enumerate() {
+        let source = map.source_range();
+        if source.is_empty() {
+            continue;
+        }
+        for piece in subtract(source.clone(), &covered) {
+            owned.push((piece, index));
+        }
+        covered.push(source);
+        covered = merge_ranges(covered);
+    }
+    owned.sort_by_key(|(range, _)| range.start);
+    owned
+}
+
+// push whole ranges through one category. each range is split at the rule boundaries it
+// crosses: the pieces inside a rule are shifted, the rest pass through unchanged.
+// like map_value, the first rule that covers a value wins.
+pub fn map_ranges(ranges: &[Range<u64>], maps: &[Map]) -> Vec<Range<u64>> {
+    let mut mapped = Vec::new();
+    let mut unmapped: Vec<Range<u64>> = ranges.to_vec();
+
+    for map in maps {
+        let Range {
+            start: source_start,
+            end: source_end,
+        } = map.source_range();
+
+        let mut remaining = Vec::new();
+        for range in unmapped {
+            // the part before and after the rule stays for the next rules.
+            let before = range.start..range.end.min(source_start);
+            let inside = range.start.max(source_start)..range.end.min(source_end);
+            let after = range.start.max(source_end)..range.end;
+
+            if !before.is_empty() {
+                remaining.push(before);
+            }
+            if !inside.is_empty() {
+                let offset = inside.start - source_start;
+                let destination_start = map.destination_range_start + offset;
+                mapped.push(destination_start..destination_start + (inside.end - inside.start));
+            }
+            if !after.is_empty() {
+                remaining.push(after);
+            }
+        }
+        unmapped = remaining;
+    }
+
+    mapped.extend(unmapped);
+    merge_ranges(mapped)
+}
+
+// run the seed ranges through every category and return the smallest location reached.
+pub fn minimum_location(almanac: &Almanac, map_names: &[&str]) -> Option<u64> {
+    let mut ranges = merge_ranges(almanac.seeds.clone());
+    for map_name in map_names {
+        let index = MapIndex::new(almanac.maps.get(*map_name)?);
+        ranges = index.map_ranges(&ranges);
+    }
+    ranges.first().map(|range| range.start)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteMinimum {
+    pub map_names: Vec<String>,
+    // None when no seed gets through the route.
+    pub minimum: Option<u64>,
+}
+
+// the smallest value reached over every route from one category to another, shortest route
+// first, for at most `limit` routes. the overall minimum is the smallest of them.
+pub fn minimum_per_route(
+    almanac: &Almanac,
+    from: &str,
+    to: &str,
+    limit: usize,
+) -> Vec<RouteMinimum> {
+    almanac
+        .routes(from, to, limit)
+        .into_iter()
+        .map(|map_names| {
+            let names: Vec<&str> = map_names.iter().map(String::as_str).collect();
+            RouteMinimum {
+                minimum: minimum_location(almanac, &names),
+                map_names,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_ranges() {
+        let maps = vec![
+            Map {
+                destination_range_start: 50,
+                source_range_start: 98,
+                range_length: 2,
+            },
+            Map {
+                destination_range_start: 52,
+                source_range_start: 50,
+                range_length: 48,
+            },
+        ];
+        // 79..93 is fully inside the second rule, 40..55 is split at 50.
+        assert_eq!(map_ranges(&[79..93], &maps), vec![81..95]);
+        assert_eq!(map_ranges(&[40..55], &maps), vec![40..50, 52..57]);
+        // 96..102 touches both rules and the identity area above 100.
+        assert_eq!(map_ranges(&[96..102], &maps), vec![50..52, 98..102]);
+
+        // every value of the mapped ranges must agree with the per-value lookup.
+        let seeds = vec![0..10, 45..60, 90..110];
+        let expected = merge_ranges(
+            seeds
+                .iter()
+                .flat_map(|range| range.clone())
+                .map(|seed| {
+                    let location = crate::map_value(seed, &maps);
+                    location..location + 1
+                })
+                .collect(),
+        );
+        assert_eq!(map_ranges(&seeds, &maps), expected);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::compose::PiecewiseMap;
+use crate::ranges::merge_ranges;
+use crate::{map_value, Almanac, Map};
+use std::ops::Range;
+
+// inverse of map_single_value: the source value that this rule sends to `value`, if any.
+pub fn unmap_single_value(value: u64, map: &Map) -> Option<u64> {
+    if map.destination_range().contains(&value) {
+        let offset = value - map.destination_range_start;
+        Some(map.source_range_start + offset)
+    } else {
+        None
+    }
+}
+
+// every source value that map_value sends to `value`. there can be several: one per rule
+// whose destination covers the value, plus the value itself when no rule covers it as a source.
+pub fn unmap_value(value: u64, maps: &[Map]) -> Vec<u64> {
+    let mut sources: Vec<u64> = maps
+        .iter()
+        .filter_map(|map| unmap_single_value(value, map))
+        .chain(std::iter::once(value))
+        // a candidate only counts if the forward lookup really ends up at the value,
+        // e.g. an earlier rule may shadow the one we came through.
+        .filter(|&source| map_value(source, maps) == value)
+        .collect();
+    sources.sort();
+    sources.dedup();
+    sources
+}
+
+// every source range that lands inside the given destination ranges.
+pub fn unmap_ranges(ranges: &[Range<u64>], maps: &[Map]) -> Vec<Range<u64>> {
+    preimage(&PiecewiseMap::from_maps(maps), ranges)
+}
+
+pub fn preimage(function: &PiecewiseMap, ranges: &[Range<u64>]) -> Vec<Range<u64>> {
+    let mut sources = Vec::new();
+    for segment in function.segments() {
+        let image = segment.destination();
+        for range in ranges {
+            let overlap = image.start.max(range.start)..image.end.min(range.end);
+            if overlap.is_empty() {
+                continue;
+            }
+            let start = segment.source.start + (overlap.start - image.start);
+            sources.push(start..start + (overlap.end - overlap.start));
+        }
+    }
+    merge_ranges(sources)
+}
+
+// which seeds (as ranges) end up in the given location ranges. the seeds listed in the
+// almanac are not taken into account.
+pub fn seeds_for_locations(
+    almanac: &Almanac,
+    map_names: &[&str],
+    locations: &[Range<u64>],
+) -> Option<Vec<Range<u64>>> {
+    let mut ranges = merge_ranges(locations.to_vec());
+    for map_name in map_names.iter().rev() {
+        ranges = unmap_ranges(&ranges, almanac.maps.get(*map_name)?);
+    }
+    Some(ranges)
+}
+
+// alternative search: try the locations from 0 upwards and stop at the first one that
+// can be traced back to one of the almanac seeds.
+pub fn minimum_location_by_reverse_scan(almanac: &Almanac, map_names: &[&str]) -> Option<u64> {
+    let maps = map_names
+        .iter()
+        .rev()
+        .map(|map_name| almanac.maps.get(*map_name))
+        .collect::<Option<Vec<_>>>()?;
+    if almanac.seeds.iter().all(|range| range.is_empty()) {
+        return None;
+    }
+
+    (0..u64::MAX).find(|&location| {
+        let mut values = vec![location];
+        for maps in &maps {
+            values = values
+                .iter()
+                .flat_map(|&value| unmap_value(value, maps))
+                .collect();
+        }
+        values
+            .iter()
+            .any(|seed| almanac.seeds.iter().any(|range| range.contains(seed)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unmap_value() {
+        let maps = vec![
+            Map {
+                destination_range_start: 50,
+                source_range_start: 98,
+                range_length: 2,
+            },
+            Map {
+                destination_range_start: 52,
+                source_range_start: 50,
+                range_length: 48,
+            },
+        ];
+        // 51 comes from 99; 51 itself is mapped elsewhere, so it is not its own preimage.
+        assert_eq!(unmap_value(51, &maps), vec![99]);
+        // 55 comes from 53, and 55 does not map to itself.
+        assert_eq!(unmap_value(55, &maps), vec![53]);
+        // 99 comes from 97, and 99 is covered by the first rule.
+        assert_eq!(unmap_value(99, &maps), vec![97]);
+        // 10 is identity only.
+        assert_eq!(unmap_value(10, &maps), vec![10]);
+
+        // 45..55 back to the sources: 45..50 identity, 50..52 from 98..100, 52..55 from 50..53.
+        assert_eq!(unmap_ranges(&[45..55], &maps), vec![45..53, 98..100]);
+        for value in 0..120 {
+            let sources = unmap_value(value, &maps);
+            for source in 0..120 {
+                assert_eq!(
+                    sources.contains(&source),
+                    map_value(source, &maps) == value,
+                    "value {} source {}",
+                    value,
+                    source
+                );
+            }
+        }
+    }
+}
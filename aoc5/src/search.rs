@@ -0,0 +1,401 @@
+use crate::checkpoint::{fingerprint, Checkpoint, RangeCheckpoint};
+use crate::index::MapIndex;
+use crate::progress::Progress;
+use crate::Almanac;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::ops::Range;
+use std::sync::Mutex;
+
+// seeds per unit of work. small enough that cancellation is noticed quickly and the workers
+// stay busy until the end, large enough that the bookkeeping does not show up.
+const CHUNK_LENGTH: u64 = 1 << 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchOutcome {
+    // the smallest location among the seeds that were visited.
+    pub minimum: Option<u64>,
+    // false when the search was cancelled before every seed was visited.
+    pub complete: bool,
+}
+
+// stops at u64::MAX, two large seed ranges can hold more seeds than that.
+pub fn seed_count(almanac: &Almanac) -> u64 {
+    almanac.seeds.iter().fold(0, |total: u64, range| {
+        total.saturating_add(range.end - range.start)
+    })
+}
+
+fn chunks(range: Range<u64>) -> impl ParallelIterator<Item = Range<u64>> {
+    let count = (range.end - range.start).div_ceil(CHUNK_LENGTH);
+    (0..count).into_par_iter().map(move |chunk| {
+        let start = range.start + chunk * CHUNK_LENGTH;
+        start..range.end.min(start.saturating_add(CHUNK_LENGTH))
+    })
+}
+
+// how far the search got in one seed range. everything below `reached` is done; chunks further
+// on that finished early wait in `finished` (start -> end) until the gap in front of them closes.
+#[derive(Debug)]
+struct RangeState {
+    range: Range<u64>,
+    reached: u64,
+    finished: BTreeMap<u64, u64>,
+}
+
+// the part of a search that is saved in a checkpoint and picked up again by `resume`.
+#[derive(Debug)]
+pub struct SearchState {
+    fingerprint: u64,
+    ranges: Vec<Mutex<RangeState>>,
+    best: Option<u64>,
+}
+
+impl SearchState {
+    pub fn new(almanac: &Almanac, map_names: &[&str]) -> Self {
+        let ranges = almanac
+            .seeds
+            .iter()
+            .map(|range| RangeCheckpoint {
+                start: range.start,
+                end: range.end,
+                reached: range.start,
+            })
+            .collect();
+        SearchState::with_ranges(fingerprint(almanac, map_names), ranges, None)
+    }
+
+    // continue a search from a checkpoint that was written for the same almanac and chain.
+    pub fn from_checkpoint(
+        almanac: &Almanac,
+        map_names: &[&str],
+        checkpoint: &Checkpoint,
+    ) -> Result<Self, Box<dyn Error>> {
+        if checkpoint.fingerprint != fingerprint(almanac, map_names) {
+            return Err("the checkpoint belongs to a different almanac or chain".into());
+        }
+        let consistent = checkpoint.ranges.len() == almanac.seeds.len()
+            && checkpoint
+                .ranges
+                .iter()
+                .zip(&almanac.seeds)
+                .all(|(saved, seeds)| {
+                    saved.start == seeds.start
+                        && saved.end == seeds.end
+                        && (saved.start..=saved.end).contains(&saved.reached)
+                });
+        if !consistent {
+            return Err("the checkpoint does not match the seed ranges".into());
+        }
+        Ok(SearchState::with_ranges(
+            checkpoint.fingerprint,
+            checkpoint.ranges.clone(),
+            checkpoint.best,
+        ))
+    }
+
+    fn with_ranges(fingerprint: u64, ranges: Vec<RangeCheckpoint>, best: Option<u64>) -> Self {
+        let ranges = ranges
+            .into_iter()
+            .map(|saved| {
+                Mutex::new(RangeState {
+                    range: saved.start..saved.end,
+                    reached: saved.reached,
+                    finished: BTreeMap::new(),
+                })
+            })
+            .collect();
+        SearchState {
+            fingerprint,
+            ranges,
+            best,
+        }
+    }
+
+    // seeds that are done for good, i.e. below `reached`.
+    pub fn done(&self) -> u64 {
+        self.ranges.iter().fold(0, |done: u64, state| {
+            let state = state.lock().unwrap();
+            done.saturating_add(state.reached - state.range.start)
+        })
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.ranges.iter().all(|state| {
+            let state = state.lock().unwrap();
+            state.reached == state.range.end
+        })
+    }
+
+    // safe to call while `resume` runs with the same progress. a chunk offers its locations
+    // before it is finished, so reading the positions first and the best location after them
+    // covers every seed below `reached`.
+    pub fn checkpoint(&self, progress: &Progress) -> Checkpoint {
+        let ranges = self
+            .ranges
+            .iter()
+            .map(|state| {
+                let state = state.lock().unwrap();
+                RangeCheckpoint {
+                    start: state.range.start,
+                    end: state.range.end,
+                    reached: state.reached,
+                }
+            })
+            .collect();
+        Checkpoint {
+            fingerprint: self.fingerprint,
+            ranges,
+            best: progress.best(),
+        }
+    }
+
+    fn remaining(&self) -> Vec<(usize, Range<u64>)> {
+        self.ranges
+            .iter()
+            .enumerate()
+            .map(|(index, state)| {
+                let state = state.lock().unwrap();
+                (index, state.reached..state.range.end)
+            })
+            .collect()
+    }
+
+    fn finish(&self, index: usize, chunk: Range<u64>) {
+        let state = &mut *self.ranges[index].lock().unwrap();
+        state.finished.insert(chunk.start, chunk.end);
+        while let Some(end) = state.finished.remove(&state.reached) {
+            state.reached = end;
+        }
+    }
+}
+
+// the original solver: walk every single seed through every category. the seed ranges are cut
+// into chunks that the rayon workers pick up one by one; once the progress is cancelled no new
+// chunk is started, and the minimum over the finished chunks is returned. None when a map of
+// the chain is missing.
+pub fn search(almanac: &Almanac, map_names: &[&str], progress: &Progress) -> Option<SearchOutcome> {
+    resume(
+        almanac,
+        map_names,
+        &SearchState::new(almanac, map_names),
+        progress,
+    )
+}
+
+// the same search, skipping the seeds that `state` has already visited and recording the ones
+// visited now. `progress` counts the seeds of the whole almanac, see Progress::with_done.
+pub fn resume(
+    almanac: &Almanac,
+    map_names: &[&str],
+    state: &SearchState,
+    progress: &Progress,
+) -> Option<SearchOutcome> {
+    // look the rules up with a binary search, the inputs have dozens of rules per category.
+    let indexes = map_names
+        .iter()
+        .map(|map_name| Some(MapIndex::new(almanac.maps.get(*map_name)?)))
+        .collect::<Option<Vec<_>>>()?;
+    if let Some(best) = state.best {
+        progress.offer(best);
+    }
+
+    state
+        .remaining()
+        .into_par_iter()
+        .flat_map(|(index, range)| chunks(range).map(move |chunk| (index, chunk)))
+        // stop handing out chunks instead of skipping them, a huge seed range has billions.
+        .map(|work| (!progress.is_cancelled()).then_some(work))
+        .while_some()
+        .for_each(|(index, chunk)| {
+            let length = chunk.end - chunk.start;
+            let smallest = chunk
+                .clone()
+                .map(|seed| {
+                    indexes
+                        .iter()
+                        .fold(seed, |value, index| index.lookup(value))
+                })
+                .min();
+            if let Some(smallest) = smallest {
+                progress.offer(smallest);
+            }
+            state.finish(index, chunk);
+            progress.add(length);
+        });
+
+    Some(SearchOutcome {
+        minimum: progress.best(),
+        complete: state.is_complete(),
+    })
+}
+
+// the search without progress output or cancellation.
+pub fn brute_force_minimum(almanac: &Almanac, map_names: &[&str]) -> Option<u64> {
+    search(almanac, map_names, &Progress::new(seed_count(almanac)))?.minimum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkpoint::CheckpointFile;
+    use crate::generate::{generate_almanac, location_chain, GeneratorConfig};
+    use crate::ranges;
+    use std::thread;
+    use std::time::Duration;
+
+    // a few chunks per seed range.
+    fn large_almanac() -> Almanac {
+        let config = GeneratorConfig {
+            max_seed_range_length: CHUNK_LENGTH * 3,
+            value_limit: 1 << 32,
+            ..Default::default()
+        };
+        generate_almanac(&config, 3)
+    }
+
+    #[test]
+    fn test_search_cancellation() {
+        let almanac = large_almanac();
+        let map_names = location_chain(&almanac);
+        let total = seed_count(&almanac);
+
+        let progress = Progress::new(total);
+        assert_eq!(
+            search(&almanac, &map_names, &progress),
+            Some(SearchOutcome {
+                minimum: ranges::minimum_location(&almanac, &map_names),
+                complete: true,
+            })
+        );
+        assert_eq!(progress.done(), total);
+
+        // cancelled before the start: nothing is visited.
+        let progress = Progress::new(total);
+        progress.cancel();
+        assert_eq!(
+            search(&almanac, &map_names, &progress),
+            Some(SearchOutcome {
+                minimum: None,
+                complete: false,
+            })
+        );
+
+        let progress = Progress::new(total).with_timeout(Duration::ZERO);
+        assert!(!search(&almanac, &map_names, &progress).unwrap().complete);
+
+        // a chain through a map the almanac does not have.
+        let progress = Progress::new(total);
+        assert_eq!(search(&almanac, &["seed-to-nowhere"], &progress), None);
+        assert_eq!(brute_force_minimum(&almanac, &["seed-to-nowhere"]), None);
+
+        // more seeds than fit into a u64.
+        let huge = Almanac {
+            seeds: vec![0..u64::MAX, 5..u64::MAX - 5],
+            ..almanac.clone()
+        };
+        assert_eq!(seed_count(&huge), u64::MAX);
+        let progress = Progress::new(seed_count(&huge)).with_timeout(Duration::from_millis(20));
+        assert!(!search(&huge, &map_names, &progress).unwrap().complete);
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        let almanac = large_almanac();
+        let map_names = location_chain(&almanac);
+        let total = seed_count(&almanac);
+        let path =
+            std::env::temp_dir().join(format!("aoc5-checkpoint-{}.json", std::process::id()));
+
+        // chunks that finish out of order only count once the gap before them is closed.
+        let state = SearchState::new(&almanac, &map_names);
+        let start = almanac.seeds[0].start;
+        state.finish(0, start + 10..start + 20);
+        assert_eq!(state.done(), 0);
+        state.finish(0, start..start + 10);
+        assert_eq!(state.done(), 20);
+
+        // stop after a few milliseconds each time, until the search is through. the file also
+        // holds the search of a shorter chain, which is left alone.
+        let key = fingerprint(&almanac, &map_names);
+        let other = SearchState::new(&almanac, &map_names[1..]).checkpoint(&Progress::new(total));
+        let mut file = CheckpointFile::default();
+        file.insert(other.clone());
+        file.save(&path).unwrap();
+        let mut state = SearchState::new(&almanac, &map_names);
+        let outcome = loop {
+            let progress = Progress::new(total)
+                .with_done(state.done())
+                .with_timeout(Duration::from_millis(20));
+            let outcome = resume(&almanac, &map_names, &state, &progress).unwrap();
+            let mut file = CheckpointFile::load(&path).unwrap();
+            file.insert(state.checkpoint(&progress));
+            file.save(&path).unwrap();
+            if outcome.complete {
+                break outcome;
+            }
+            let file = CheckpointFile::load(&path).unwrap();
+            assert_eq!(file.searches.len(), 2);
+            let checkpoint = file.get(key).unwrap();
+            assert!(!checkpoint.is_complete());
+            state = SearchState::from_checkpoint(&almanac, &map_names, checkpoint).unwrap();
+        };
+        assert_eq!(outcome.minimum, brute_force_minimum(&almanac, &map_names));
+        let mut file = CheckpointFile::load(&path).unwrap();
+        assert!(file.get(key).unwrap().is_complete());
+        assert_eq!(file.get(other.fingerprint), Some(&other));
+
+        // a checkpoint only fits the almanac it was written for.
+        assert!(SearchState::from_checkpoint(&almanac, &map_names, &other).is_err());
+
+        // the file goes away with the last search in it.
+        file.remove(key);
+        file.save(&path).unwrap();
+        assert!(path.exists());
+        file.remove(other.fingerprint);
+        file.save(&path).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_checkpoint_while_searching() {
+        let almanac = large_almanac();
+        let map_names = location_chain(&almanac);
+        let state = SearchState::new(&almanac, &map_names);
+        let progress = Progress::new(seed_count(&almanac));
+
+        // the snapshots the saver thread would write, taken while the workers are busy.
+        let snapshots = thread::scope(|scope| {
+            let saver = scope.spawn(|| {
+                let mut snapshots = Vec::new();
+                while !state.is_complete() {
+                    snapshots.push(state.checkpoint(&progress));
+                    thread::sleep(Duration::from_millis(1));
+                }
+                snapshots
+            });
+            assert!(
+                resume(&almanac, &map_names, &state, &progress)
+                    .unwrap()
+                    .complete
+            );
+            saver.join().unwrap()
+        });
+
+        // each one knows a location at least as small as that of every seed it marks as done.
+        for checkpoint in snapshots {
+            let visited = Almanac {
+                seeds: checkpoint
+                    .ranges
+                    .iter()
+                    .map(|range| range.start..range.reached)
+                    .collect(),
+                ..almanac.clone()
+            };
+            if let Some(minimum) = ranges::minimum_location(&visited, &map_names) {
+                assert!(checkpoint.best.is_some_and(|best| best <= minimum));
+            }
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::compose::PiecewiseMap;
+use crate::ranges::merge_ranges;
+use crate::Almanac;
+use std::ops::Range;
+
+// a stretch of seeds that the chain moves by one fixed offset onto `locations`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocationPiece {
+    pub seeds: Range<u64>,
+    pub locations: Range<u64>,
+}
+
+impl LocationPiece {
+    fn seed_for(&self, location: u64) -> u64 {
+        self.seeds.start + (location - self.locations.start)
+    }
+}
+
+// the locations that the almanac seeds reach, worked out on ranges with the composed
+// function, so that the part 2 inputs need no more work than the examples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocationStats {
+    // sorted by the first location.
+    pieces: Vec<LocationPiece>,
+    // sorted and merged.
+    locations: Vec<Range<u64>>,
+}
+
+impl LocationStats {
+    pub fn from_chain(almanac: &Almanac, map_names: &[&str]) -> Option<Self> {
+        let function = PiecewiseMap::from_chain(almanac, map_names)?;
+        // a seed listed twice still reaches its location only once.
+        let seeds = merge_ranges(almanac.seeds.clone());
+
+        let mut pieces = Vec::new();
+        for segment in function.segments() {
+            for range in &seeds {
+                let overlap =
+                    segment.source.start.max(range.start)..segment.source.end.min(range.end);
+                if overlap.is_empty() {
+                    continue;
+                }
+                let start = segment.destination_start + (overlap.start - segment.source.start);
+                pieces.push(LocationPiece {
+                    locations: start..start + (overlap.end - overlap.start),
+                    seeds: overlap,
+                });
+            }
+        }
+        pieces.sort_by_key(|piece| (piece.locations.start, piece.seeds.start));
+        let locations = merge_ranges(pieces.iter().map(|piece| piece.locations.clone()).collect());
+        Some(LocationStats { pieces, locations })
+    }
+
+    pub fn pieces(&self) -> &[LocationPiece] {
+        &self.pieces
+    }
+
+    // every location that some seed reaches, as sorted and merged ranges.
+    pub fn locations(&self) -> &[Range<u64>] {
+        &self.locations
+    }
+
+    pub fn minimum(&self) -> Option<u64> {
+        self.locations.first().map(|range| range.start)
+    }
+
+    pub fn maximum(&self) -> Option<u64> {
+        self.locations.last().map(|range| range.end - 1)
+    }
+
+    // several seeds can end up on the same location, so this can be less than the seed count.
+    pub fn distinct_count(&self) -> u64 {
+        self.locations
+            .iter()
+            .map(|range| range.end - range.start)
+            .sum()
+    }
+
+    // the `count` smallest locations, each with the sorted seeds that reach it.
+    pub fn smallest(&self, count: usize) -> Vec<(u64, Vec<u64>)> {
+        self.locations
+            .iter()
+            .flat_map(|range| range.clone())
+            .take(count)
+            .map(|location| {
+                let mut seeds: Vec<u64> = self
+                    .pieces
+                    .iter()
+                    .take_while(|piece| piece.locations.start <= location)
+                    .filter(|piece| piece.locations.contains(&location))
+                    .map(|piece| piece.seed_for(location))
+                    .collect();
+                seeds.sort();
+                (location, seeds)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{generated_cases, GeneratorConfig};
+    use crate::map_value;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_stats_match_every_seed() {
+        for case in generated_cases(300, GeneratorConfig::default()) {
+            let (almanac, map_names) = (&case.almanac, case.map_names());
+            let stats = LocationStats::from_chain(almanac, &map_names).unwrap();
+
+            let mut seeds_by_location: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+            for seed in merge_ranges(almanac.seeds.clone()).into_iter().flatten() {
+                let location = map_names.iter().fold(seed, |value, map_name| {
+                    map_value(value, &almanac.maps[*map_name])
+                });
+                seeds_by_location.entry(location).or_default().push(seed);
+            }
+
+            let expected_locations: Vec<u64> = seeds_by_location.keys().copied().collect();
+            let locations: Vec<u64> = stats.locations().iter().cloned().flatten().collect();
+            assert_eq!(locations, expected_locations, "case {}", case.seed);
+            assert_eq!(stats.minimum(), expected_locations.first().copied());
+            assert_eq!(stats.maximum(), expected_locations.last().copied());
+            assert_eq!(stats.distinct_count(), expected_locations.len() as u64);
+
+            let expected: Vec<(u64, Vec<u64>)> = seeds_by_location.into_iter().take(5).collect();
+            assert_eq!(stats.smallest(5), expected, "case {}", case.seed);
+        }
+    }
+}
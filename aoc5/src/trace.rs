@@ -0,0 +1,90 @@
+use crate::{map_single_value, Almanac};
+use serde::Serialize;
+use std::fmt;
+
+// the rule of a category that moved the value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleHit {
+    // position of the rule in the category, as listed in the almanac.
+    pub index: usize,
+    pub source_range_start: u64,
+    pub offset: i128,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraceStep {
+    pub map_name: String,
+    pub input: u64,
+    pub output: u64,
+    // None when no rule covered the input and it passed through unchanged.
+    pub rule: Option<RuleHit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Trace {
+    pub seed: u64,
+    pub steps: Vec<TraceStep>,
+}
+
+impl Trace {
+    pub fn result(&self) -> u64 {
+        self.steps.last().map_or(self.seed, |step| step.output)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+// follow one seed through the chain and record which rule fired in every category.
+// the rule is picked the same way as in map_value: the first one that covers the value.
+pub fn trace_seed(almanac: &Almanac, map_names: &[&str], seed: u64) -> Option<Trace> {
+    let mut steps = Vec::new();
+    let mut value = seed;
+    for map_name in map_names {
+        let maps = almanac.maps.get(*map_name)?;
+        let hit = maps.iter().enumerate().find_map(|(index, map)| {
+            map_single_value(value, map).map(|output| (index, map, output))
+        });
+
+        let step = match hit {
+            Some((index, map, output)) => TraceStep {
+                map_name: map_name.to_string(),
+                input: value,
+                output,
+                rule: Some(RuleHit {
+                    index,
+                    source_range_start: map.source_range_start,
+                    offset: map.destination_range_start as i128 - map.source_range_start as i128,
+                }),
+            },
+            None => TraceStep {
+                map_name: map_name.to_string(),
+                input: value,
+                output: value,
+                rule: None,
+            },
+        };
+        value = step.output;
+        steps.push(step);
+    }
+    Some(Trace { seed, steps })
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "seed {}", self.seed)?;
+        for step in &self.steps {
+            write!(f, "  {}: {} -> {}", step.map_name, step.input, step.output)?;
+            match &step.rule {
+                Some(rule) => writeln!(
+                    f,
+                    " (rule {}, source start {}, offset {:+})",
+                    rule.index, rule.source_range_start, rule.offset
+                )?,
+                None => writeln!(f, " (no rule, identity)")?,
+            }
+        }
+        writeln!(f, "result {}", self.result())
+    }
+}
@@ -0,0 +1,229 @@
+use crate::compose::PiecewiseMap;
+use crate::Almanac;
+use std::fmt;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+// the typed view of the puzzle categories. every category gets its own value type and every
+// map is a function from one of them to the next, so a soil number can not be fed into the
+// humidity-to-location map by accident. almanacs with other categories keep using the
+// string-keyed Almanac. the bounds let the value types derive everything a plain u64 has.
+pub trait Category: fmt::Debug + Copy + Ord + Hash {
+    const NAME: &'static str;
+}
+
+macro_rules! categories {
+    ($($category:ident => $name:literal),* $(,)?) => {
+        $(
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+            pub struct $category;
+
+            impl Category for $category {
+                const NAME: &'static str = $name;
+            }
+        )*
+    };
+}
+
+categories! {
+    Seed => "seed",
+    Soil => "soil",
+    Fertilizer => "fertilizer",
+    Water => "water",
+    Light => "light",
+    Temperature => "temperature",
+    Humidity => "humidity",
+    Location => "location",
+}
+
+// a number of the category C.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Value<C> {
+    value: u64,
+    category: PhantomData<C>,
+}
+
+impl<C: Category> Value<C> {
+    pub fn new(value: u64) -> Self {
+        Value {
+            value,
+            category: PhantomData,
+        }
+    }
+
+    pub fn get(self) -> u64 {
+        self.value
+    }
+}
+
+impl<C: Category> fmt::Display for Value<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", C::NAME, self.value)
+    }
+}
+
+// the map from one category to the next, or a chain of them folded into one function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedMap<From, To> {
+    function: PiecewiseMap,
+    categories: PhantomData<(From, To)>,
+}
+
+impl<From: Category, To: Category> TypedMap<From, To> {
+    // the "<from>-to-<to>" map of the almanac.
+    pub fn from_almanac(almanac: &Almanac) -> Option<Self> {
+        let maps = almanac
+            .maps
+            .get(&format!("{}-to-{}", From::NAME, To::NAME))?;
+        Some(TypedMap::from_function(PiecewiseMap::from_maps(maps)))
+    }
+
+    fn from_function(function: PiecewiseMap) -> Self {
+        TypedMap {
+            function,
+            categories: PhantomData,
+        }
+    }
+
+    pub fn function(&self) -> &PiecewiseMap {
+        &self.function
+    }
+
+    pub fn apply(&self, value: Value<From>) -> Value<To> {
+        Value::new(self.function.evaluate(value.get()))
+    }
+
+    /// First this map, then `next`. The chain has to line up:
+    ///
+    /// ```compile_fail
+    /// use aoc5::typed::{Humidity, Location, Seed, Soil, TypedMap};
+    /// fn wrong(a: TypedMap<Seed, Soil>, b: TypedMap<Humidity, Location>) {
+    ///     a.then(&b);
+    /// }
+    /// ```
+    pub fn then<Next: Category>(&self, next: &TypedMap<To, Next>) -> TypedMap<From, Next> {
+        TypedMap::from_function(self.function.then(&next.function))
+    }
+
+    pub fn minimum_over(&self, ranges: &[Range<Value<From>>]) -> Option<Value<To>> {
+        let ranges: Vec<Range<u64>> = ranges
+            .iter()
+            .map(|range| range.start.get()..range.end.get())
+            .collect();
+        self.function.minimum_over(&ranges).map(Value::new)
+    }
+}
+
+// the puzzle almanac with every category typed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedAlmanac {
+    pub seeds: Vec<Range<Value<Seed>>>,
+    pub seed_to_soil: TypedMap<Seed, Soil>,
+    pub soil_to_fertilizer: TypedMap<Soil, Fertilizer>,
+    pub fertilizer_to_water: TypedMap<Fertilizer, Water>,
+    pub water_to_light: TypedMap<Water, Light>,
+    pub light_to_temperature: TypedMap<Light, Temperature>,
+    pub temperature_to_humidity: TypedMap<Temperature, Humidity>,
+    pub humidity_to_location: TypedMap<Humidity, Location>,
+}
+
+impl TypedAlmanac {
+    // None when one of the seven puzzle maps is missing.
+    pub fn from_almanac(almanac: &Almanac) -> Option<Self> {
+        Some(TypedAlmanac {
+            seeds: almanac
+                .seeds
+                .iter()
+                .map(|range| Value::new(range.start)..Value::new(range.end))
+                .collect(),
+            seed_to_soil: TypedMap::from_almanac(almanac)?,
+            soil_to_fertilizer: TypedMap::from_almanac(almanac)?,
+            fertilizer_to_water: TypedMap::from_almanac(almanac)?,
+            water_to_light: TypedMap::from_almanac(almanac)?,
+            light_to_temperature: TypedMap::from_almanac(almanac)?,
+            temperature_to_humidity: TypedMap::from_almanac(almanac)?,
+            humidity_to_location: TypedMap::from_almanac(almanac)?,
+        })
+    }
+
+    pub fn seed_to_location(&self) -> TypedMap<Seed, Location> {
+        self.seed_to_soil
+            .then(&self.soil_to_fertilizer)
+            .then(&self.fertilizer_to_water)
+            .then(&self.water_to_light)
+            .then(&self.light_to_temperature)
+            .then(&self.temperature_to_humidity)
+            .then(&self.humidity_to_location)
+    }
+
+    pub fn location(&self, seed: Value<Seed>) -> Value<Location> {
+        let soil = self.seed_to_soil.apply(seed);
+        let fertilizer = self.soil_to_fertilizer.apply(soil);
+        let water = self.fertilizer_to_water.apply(fertilizer);
+        let light = self.water_to_light.apply(water);
+        let temperature = self.light_to_temperature.apply(light);
+        let humidity = self.temperature_to_humidity.apply(temperature);
+        self.humidity_to_location.apply(humidity)
+    }
+
+    pub fn minimum_location(&self) -> Option<Value<Location>> {
+        self.seed_to_location().minimum_over(&self.seeds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{
+        category_names, generate_almanac, generated_cases, location_chain, GeneratedCase,
+        GeneratorConfig,
+    };
+    use crate::{map_value, ranges};
+
+    #[test]
+    fn test_typed_matches_dynamic() {
+        for GeneratedCase { mut almanac, .. } in generated_cases(100, GeneratorConfig::default()) {
+            // the generator numbers its categories, the typed model wants the puzzle names.
+            let names = [
+                "seed",
+                "soil",
+                "fertilizer",
+                "water",
+                "light",
+                "temperature",
+                "humidity",
+                "location",
+            ];
+            let generated = category_names(names.len());
+            almanac.maps = generated
+                .windows(2)
+                .zip(names.windows(2))
+                .map(|(from, to)| {
+                    let maps = almanac.maps[&format!("{}-to-{}", from[0], from[1])].clone();
+                    (format!("{}-to-{}", to[0], to[1]), maps)
+                })
+                .collect();
+            let map_names = location_chain(&almanac);
+
+            let typed = TypedAlmanac::from_almanac(&almanac).unwrap();
+            assert_eq!(
+                typed.minimum_location().map(Value::get),
+                ranges::minimum_location(&almanac, &map_names)
+            );
+            let composed = typed.seed_to_location();
+            for seed in 0..200 {
+                let expected = map_names.iter().fold(seed, |value, map_name| {
+                    map_value(value, &almanac.maps[*map_name])
+                });
+                assert_eq!(typed.location(Value::new(seed)).get(), expected);
+                assert_eq!(composed.apply(Value::new(seed)).get(), expected);
+            }
+        }
+
+        let mut almanac = generate_almanac(&GeneratorConfig::default(), 0);
+        almanac.maps.clear();
+        assert_eq!(TypedAlmanac::from_almanac(&almanac), None);
+        assert_eq!(Value::<Soil>::new(81).to_string(), "soil 81");
+    }
+}
@@ -0,0 +1,201 @@
+use crate::ranges::merge_ranges;
+use crate::{Almanac, Map};
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+// two rules of the same category (by index) that share the given range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overlap {
+    pub first: usize,
+    pub second: usize,
+    pub range: Range<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapReport {
+    pub map_name: String,
+    // the same source value is covered by two rules; map_value lets the first one win.
+    pub source_overlaps: Vec<Overlap>,
+    // two rules produce the same destination value, so the mapping is not injective.
+    pub destination_overlaps: Vec<Overlap>,
+    // source values no rule covers, including those below the first and above the last rule.
+    // they are passed through unchanged.
+    pub gaps: Vec<Range<u64>>,
+}
+
+impl MapReport {
+    pub fn is_ambiguous(&self) -> bool {
+        !self.source_overlaps.is_empty() || !self.destination_overlaps.is_empty()
+    }
+}
+
+fn overlaps(maps: &[Map], range_of: fn(&Map) -> Range<u64>) -> Vec<Overlap> {
+    let mut overlaps = Vec::new();
+    for (first, first_map) in maps.iter().enumerate() {
+        for (second, second_map) in maps.iter().enumerate().skip(first + 1) {
+            let a = range_of(first_map);
+            let b = range_of(second_map);
+            let range = a.start.max(b.start)..a.end.min(b.end);
+            if !range.is_empty() {
+                overlaps.push(Overlap {
+                    first,
+                    second,
+                    range,
+                });
+            }
+        }
+    }
+    overlaps
+}
+
+pub fn validate_maps(map_name: &str, maps: &[Map]) -> MapReport {
+    let covered = merge_ranges(maps.iter().map(Map::source_range).collect());
+    let mut gaps = Vec::new();
+    let mut position = 0;
+    for range in covered {
+        if position < range.start {
+            gaps.push(position..range.start);
+        }
+        position = range.end;
+    }
+    if position < u64::MAX {
+        gaps.push(position..u64::MAX);
+    }
+
+    MapReport {
+        map_name: map_name.to_string(),
+        source_overlaps: overlaps(maps, Map::source_range),
+        destination_overlaps: overlaps(maps, Map::destination_range),
+        gaps,
+    }
+}
+
+#[derive(Debug)]
+pub struct ValidationError {
+    pub reports: Vec<MapReport>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "ambiguous almanac:")?;
+        for report in &self.reports {
+            write!(f, "{}", report)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ValidationError {}
+
+// reports for every category, sorted by map name. in strict mode, an almanac with overlapping
+// source or destination ranges is rejected.
+pub fn validate_almanac(
+    almanac: &Almanac,
+    strict: bool,
+) -> Result<Vec<MapReport>, ValidationError> {
+    let mut map_names: Vec<&String> = almanac.maps.keys().collect();
+    map_names.sort();
+
+    let reports: Vec<MapReport> = map_names
+        .into_iter()
+        .map(|map_name| validate_maps(map_name, &almanac.maps[map_name]))
+        .collect();
+
+    if strict && reports.iter().any(MapReport::is_ambiguous) {
+        return Err(ValidationError {
+            reports: reports
+                .into_iter()
+                .filter(MapReport::is_ambiguous)
+                .collect(),
+        });
+    }
+    Ok(reports)
+}
+
+impl fmt::Display for MapReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}:", self.map_name)?;
+        if !self.is_ambiguous() && self.gaps.is_empty() {
+            writeln!(f, "  no overlaps or gaps")?;
+        }
+        for overlap in &self.source_overlaps {
+            writeln!(
+                f,
+                "  rules {} and {} overlap in source [{}, {})",
+                overlap.first, overlap.second, overlap.range.start, overlap.range.end
+            )?;
+        }
+        for overlap in &self.destination_overlaps {
+            writeln!(
+                f,
+                "  rules {} and {} overlap in destination [{}, {})",
+                overlap.first, overlap.second, overlap.range.start, overlap.range.end
+            )?;
+        }
+        for gap in &self.gaps {
+            writeln!(f, "  gap [{}, {}) maps to itself", gap.start, gap.end)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_maps() {
+        let maps = vec![
+            Map {
+                destination_range_start: 100,
+                source_range_start: 0,
+                range_length: 10,
+            },
+            Map {
+                destination_range_start: 105,
+                source_range_start: 5,
+                range_length: 10,
+            },
+            Map {
+                destination_range_start: 200,
+                source_range_start: 20,
+                range_length: 5,
+            },
+        ];
+        let report = validate_maps("a-to-b", &maps);
+        assert_eq!(
+            report.source_overlaps,
+            vec![Overlap {
+                first: 0,
+                second: 1,
+                range: 5..10
+            }]
+        );
+        assert_eq!(
+            report.destination_overlaps,
+            vec![Overlap {
+                first: 0,
+                second: 1,
+                range: 105..110
+            }]
+        );
+        assert_eq!(report.gaps, vec![15..20, 25..u64::MAX]);
+        assert!(report.is_ambiguous());
+
+        let mut almanac = Almanac {
+            seeds: Vec::new(),
+            maps: std::collections::HashMap::new(),
+        };
+        almanac.maps.insert("a-to-b".to_string(), maps.clone());
+        assert!(validate_almanac(&almanac, false).is_ok());
+        assert!(validate_almanac(&almanac, true).is_err());
+
+        // the identity below the first rule counts as well, and without rules it is everything.
+        assert_eq!(
+            validate_maps("a-to-b", &maps[2..]).gaps,
+            vec![0..20, 25..u64::MAX]
+        );
+        assert_eq!(validate_maps("a-to-b", &[]).gaps, vec![0..u64::MAX]);
+    }
+}
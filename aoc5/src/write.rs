@@ -0,0 +1,109 @@
+use crate::{Almanac, Map};
+use std::fmt;
+use std::io::{self, Write};
+
+// one rule line: "<destination start> <source start> <length>"
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.destination_range_start, self.source_range_start, self.range_length
+        )
+    }
+}
+
+// write the almanac in the puzzle format, with the maps in chain order. with seeds_are_ranges
+// the seeds are written as start/length pairs, otherwise every seed of every range is listed.
+pub fn write_almanac<W: Write>(
+    mut writer: W,
+    almanac: &Almanac,
+    seeds_are_ranges: bool,
+) -> io::Result<()> {
+    write!(writer, "seeds:")?;
+    for range in &almanac.seeds {
+        if seeds_are_ranges {
+            write!(writer, " {} {}", range.start, range.end - range.start)?;
+        } else {
+            for seed in range.clone() {
+                write!(writer, " {}", seed)?;
+            }
+        }
+    }
+    writeln!(writer)?;
+
+    for map_name in almanac.map_names() {
+        writeln!(writer)?;
+        writeln!(writer, "{} map:", map_name)?;
+        for map in &almanac.maps[&map_name] {
+            writeln!(writer, "{}", map)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn almanac_to_string(almanac: &Almanac, seeds_are_ranges: bool) -> String {
+    let mut buffer = Vec::new();
+    write_almanac(&mut buffer, almanac, seeds_are_ranges).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_almanac;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    // any rule that stays inside the 64-bit range.
+    fn rule() -> impl Strategy<Value = Map> {
+        (any::<u64>(), any::<u64>(), any::<u64>()).prop_map(|(destination, source, length)| Map {
+            destination_range_start: destination,
+            source_range_start: source,
+            range_length: length.min(u64::MAX - destination.max(source)),
+        })
+    }
+
+    fn almanac(seeds_are_ranges: bool) -> impl Strategy<Value = Almanac> {
+        let seed = (0..u64::MAX, any::<u64>()).prop_map(move |(start, length)| {
+            let length = if seeds_are_ranges {
+                length.min(u64::MAX - start)
+            } else {
+                1
+            };
+            start..start + length
+        });
+        let map = (
+            "[a-z]{1,8}",
+            "[a-z]{1,8}",
+            prop::collection::vec(rule(), 0..6),
+        )
+            .prop_map(|(source, destination, rules)| {
+                (format!("{}-to-{}", source, destination), rules)
+            });
+        (
+            prop::collection::vec(seed, 0..6),
+            prop::collection::vec(map, 0..6),
+        )
+            .prop_map(|(seeds, maps)| Almanac {
+                seeds,
+                maps: maps.into_iter().collect::<HashMap<_, _>>(),
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn test_write_parse_round_trip(
+            seeds_are_ranges in any::<bool>(),
+            almanac in any::<bool>().prop_flat_map(almanac),
+        ) {
+            // single seeds can only be written when every range holds exactly one seed.
+            let seeds_are_ranges = seeds_are_ranges || almanac.seeds.iter().any(|r| r.end - r.start != 1);
+            let text = almanac_to_string(&almanac, seeds_are_ranges);
+            let parsed = parse_almanac(&text, "written", seeds_are_ranges).unwrap();
+            prop_assert_eq!(&parsed, &almanac);
+            // writing the parsed almanac again gives the same text.
+            prop_assert_eq!(almanac_to_string(&parsed, seeds_are_ranges), text);
+        }
+    }
+}
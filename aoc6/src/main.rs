@@ -17,23 +17,28 @@ struct Race {
     distance: u64,
 }
 
+// number of button press times h that go further than the record, h * (T - h) > D.
+// with x = T - 2h this is x^2 < T^2 - 4D, so the winners are the x between -s and s with
+// the same parity as T, where s is the largest integer with s^2 < T^2 - 4D. all in u128
+// and integer square roots, so large races are exact.
+fn ways_to_beat(race: &Race) -> u64 {
+    let time = race.time as u128;
+    let distance = race.distance as u128;
+    let discriminant = time * time;
+    if discriminant <= 4 * distance {
+        return 0;
+    }
+    let s = (discriminant - 4 * distance - 1).isqrt();
+    let ways = if s % 2 == time % 2 { s + 1 } else { s };
+    ways as u64
+}
+
 fn calculate_total_product(races: &Vec<Race>) {
     let mut total_product: u64 = 1;
 
     for race in races {
         println!("{:?}", race);
-
-        let mut ways_to_beat = 0;
-        for button_press_time_pekka in 1..race.time {
-            let speed = button_press_time_pekka;
-            let time_to_move = race.time - button_press_time_pekka;
-            let distance = speed * time_to_move;
-
-            if distance > race.distance {
-                ways_to_beat += 1;
-            }
-        }
-        total_product *= ways_to_beat;
+        total_product *= ways_to_beat(race);
     }
     println!("Total product: {}", total_product);
 }
@@ -73,3 +78,78 @@ fn main() {
     calculate_total_product(&vec![race5]);
     calculate_total_product(&vec![race6]);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the original search over every press time.
+    fn ways_to_beat_by_loop(race: &Race) -> u64 {
+        (1..race.time)
+            .filter(|press| press * (race.time - press) > race.distance)
+            .count() as u64
+    }
+
+    #[test]
+    fn test_closed_form_matches_loop() {
+        for time in 0..120 {
+            // every record up to beyond the best possible distance, which hits the perfect
+            // squares where a press time only ties the record.
+            for distance in 0..=time * time / 4 + 2 {
+                let race = Race { time, distance };
+                assert_eq!(
+                    ways_to_beat(&race),
+                    ways_to_beat_by_loop(&race),
+                    "{:?}",
+                    race
+                );
+            }
+        }
+        for race in [
+            Race {
+                time: 7,
+                distance: 9,
+            },
+            Race {
+                time: 30,
+                distance: 200,
+            },
+            Race {
+                time: 54,
+                distance: 446,
+            },
+            Race {
+                time: 71530,
+                distance: 940200,
+            },
+        ] {
+            assert_eq!(
+                ways_to_beat(&race),
+                ways_to_beat_by_loop(&race),
+                "{:?}",
+                race
+            );
+        }
+        assert_eq!(
+            ways_to_beat(&Race {
+                time: 30,
+                distance: 200
+            }),
+            9
+        );
+        assert_eq!(
+            ways_to_beat(&Race {
+                time: 71530,
+                distance: 940200
+            }),
+            71503
+        );
+
+        // u64::MAX squared does not fit into 64 bits. every press from 2 to T - 2 wins.
+        let race = Race {
+            time: u64::MAX,
+            distance: u64::MAX,
+        };
+        assert_eq!(ways_to_beat(&race), u64::MAX - 3);
+    }
+}